@@ -0,0 +1,238 @@
+use super::{
+    rules::{Rule, Rules},
+    visitor::Visitor,
+};
+use anyhow::{anyhow, Result};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use syn::visit::Visit;
+
+#[derive(Debug)]
+pub struct Violation {
+    pub line: usize,
+    pub column: usize,
+    pub rule: Rule,
+    pub message: String,
+}
+
+fn mark_span(span: Span, covered: &mut [Vec<bool>]) {
+    let (start, end) = (span.start(), span.end());
+    for line in start.line..=end.line {
+        let Some(row) = covered.get_mut(line - 1) else {
+            continue;
+        };
+        let from = if line == start.line { start.column } else { 0 };
+        let to = if line == end.line {
+            end.column.min(row.len())
+        } else {
+            row.len()
+        };
+        for cell in row.iter_mut().take(to).skip(from) {
+            *cell = true;
+        }
+    }
+}
+
+/// Marks the characters covered by tokens, recursing into groups.
+/// Doc comments are tokens too, as `#[doc = "..."]` spanning the comment.
+fn mark_tokens(tokens: TokenStream, covered: &mut [Vec<bool>]) {
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                mark_span(group.span_open(), covered);
+                mark_span(group.span_close(), covered);
+                mark_tokens(group.stream(), covered);
+            }
+            token => mark_span(token.span(), covered),
+        }
+    }
+}
+
+/// Everything in the source that isn't a token or whitespace is a comment other than a doc comment.
+fn check_comments(source: &str, tokens: TokenStream, violations: &mut Vec<Violation>) {
+    let lines: Vec<Vec<char>> = source.lines().map(|line| line.chars().collect()).collect();
+    let mut covered: Vec<Vec<bool>> = lines.iter().map(|line| vec![false; line.len()]).collect();
+    mark_tokens(tokens, &mut covered);
+
+    // Adjacent comment lines, or a block comment, are reported once.
+    let mut in_comment = false;
+    for (line, (symbols, covered)) in lines.iter().zip(&covered).enumerate() {
+        for (column, (symbol, covered)) in symbols.iter().zip(covered).enumerate() {
+            if *covered {
+                in_comment = false;
+            } else if !symbol.is_whitespace() && !in_comment {
+                in_comment = true;
+                violations.push(Violation {
+                    line: line + 1,
+                    column: column + 1,
+                    rule: Rule::Comments,
+                    message: "only doc comments are allowed".to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn check_lines(source: &str, rules: &Rules, test_lines: usize, violations: &mut Vec<Violation>) {
+    if rules.is_enabled(Rule::LineLength) {
+        for (i, line) in source.lines().enumerate() {
+            let length = line.chars().count();
+            if length > rules.max_line_length {
+                violations.push(Violation {
+                    line: i + 1,
+                    column: rules.max_line_length + 1,
+                    rule: Rule::LineLength,
+                    message: format!(
+                        "line is {length} characters long, the limit is {}",
+                        rules.max_line_length
+                    ),
+                });
+            }
+        }
+    }
+    let lines = source.lines().count().saturating_sub(test_lines);
+    if rules.is_enabled(Rule::FileLength) && lines > rules.max_file_lines {
+        violations.push(Violation {
+            line: 1,
+            column: 1,
+            rule: Rule::FileLength,
+            message: format!(
+                "file is {lines} lines long without tests, the limit is {}",
+                rules.max_file_lines
+            ),
+        });
+    }
+}
+
+/// Checks the source of a Rust file, returning the violations ordered by position.
+pub fn check_source(source: &str, rules: &Rules) -> Result<Vec<Violation>> {
+    let syntax_error = |err: syn::Error| {
+        let start = err.span().start();
+        anyhow!("{}:{}: {err}", start.line, start.column + 1)
+    };
+    let file = syn::parse_file(source).map_err(syntax_error)?;
+    let mut visitor = Visitor::new(rules);
+    visitor.visit_file(&file);
+
+    let mut violations = visitor.violations;
+    if rules.is_enabled(Rule::Comments) {
+        let tokens = source
+            .parse::<TokenStream>()
+            .map_err(|err| syntax_error(err.into()))?;
+        check_comments(source, tokens, &mut violations);
+    }
+    check_lines(source, rules, visitor.test_lines, &mut violations);
+    violations.sort_by_key(|violation| (violation.line, violation.column));
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn rules_of(source: &str, rules: &Rules) -> Vec<(usize, Rule)> {
+        check_source(source, rules)
+            .unwrap()
+            .into_iter()
+            .map(|violation| (violation.line, violation.rule))
+            .collect()
+    }
+
+    #[test]
+    fn names_and_comments() {
+        let source = indoc! {r#"
+            //! Crate docs.
+
+            /// Reads the file.
+            fn read_file(path: &str) -> usize {
+                // count the lines
+                let text = "// not a comment";
+                let n = text.len(); /* inline */
+                for i in 0..n {
+                    let _ = i;
+                }
+                match Some(n) {
+                    Some(cnt) => cnt,
+                    None => 0,
+                }
+            }
+        "#};
+        assert_eq!(
+            rules_of(source, &Rules::default()),
+            [
+                (5, Rule::Comments),
+                (7, Rule::ShortNames),
+                (7, Rule::Comments),
+                (12, Rule::Abbreviations),
+            ]
+        );
+    }
+
+    #[test]
+    fn lengths_without_tests() {
+        let rules = Rules {
+            max_line_length: 20,
+            max_file_lines: 4,
+            max_function_lines: 3,
+            ..Rules::default()
+        };
+        let source = indoc! {"
+            fn short() {}
+
+            fn long() {
+                let _ = 1;
+                let _ = 2;
+            }
+
+            #[cfg(test)]
+            mod tests {
+                #[test]
+                fn works() {}
+            }
+        "};
+        assert_eq!(
+            rules_of(source, &rules),
+            [(1, Rule::FileLength), (3, Rule::FunctionLength)]
+        );
+        // Tests of other frameworks and tests behind more conditions are not counted either.
+        let source = indoc! {"
+            fn short() {}
+
+            #[tokio::test]
+            async fn a() {}
+
+            #[cfg(all(test,x))]
+            mod more {
+                fn b() {}
+            }
+        "};
+        assert_eq!(rules_of(source, &rules), []);
+        // Code compiled only outside of tests is counted.
+        let source = indoc! {"
+            fn short() {}
+
+            #[cfg(not(test))]
+            mod prod {
+                fn b() {}
+            }
+        "};
+        assert_eq!(rules_of(source, &rules), [(1, Rule::FileLength)]);
+        let source = "fn this_line_is_longer() {}\n";
+        assert_eq!(rules_of(source, &rules), [(1, Rule::LineLength)]);
+    }
+
+    #[test]
+    fn doc_language() {
+        let source = indoc! {"
+            /// Returns the answer.
+            /// Возвращает ответ.
+            pub fn answer() -> u32 {
+                42
+            }
+        "};
+        assert_eq!(
+            rules_of(source, &Rules::default()),
+            [(2, Rule::DocLanguage)]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use super::visitor::is_test;
+use crate::{console_println, repository::console::Console};
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    visit::Visit, Attribute, Expr, Ident, ImplItem, Item, ItemImpl, Lit, Meta, Type, Visibility,
+};
+
+/// Public item of a solution file: a struct, enum, trait, type alias, function or method.
+#[derive(Debug)]
+pub struct PublicItem {
+    pub line: usize,
+    pub kind: &'static str,
+    pub name: String,
+    pub documented: bool,
+    pub has_examples: bool,
+}
+
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(doc) if doc.path.is_ident("doc") => match &doc.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(text) => Some(text.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Collector {
+    items: Vec<PublicItem>,
+}
+
+impl Collector {
+    fn add(&mut self, kind: &'static str, ident: &Ident, name: String, attrs: &[Attribute]) {
+        let lines = doc_lines(attrs);
+        self.items.push(PublicItem {
+            line: ident.span().start().line,
+            kind,
+            name,
+            documented: lines.iter().any(|line| !line.trim().is_empty()),
+            has_examples: lines
+                .iter()
+                .any(|line| line.trim().starts_with("# Example")),
+        });
+    }
+
+    /// Methods of inherent impls, those of trait impls are documented by the trait.
+    fn add_methods(&mut self, item: &ItemImpl) {
+        if item.trait_.is_some() {
+            return;
+        }
+        let type_name = match item.self_ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| "_".to_string());
+        for impl_item in &item.items {
+            if let ImplItem::Fn(method) = impl_item {
+                if matches!(method.vis, Visibility::Public(_)) {
+                    self.add(
+                        "method",
+                        &method.sig.ident,
+                        format!("{type_name}::{}", method.sig.ident),
+                        &method.attrs,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Collector {
+    fn visit_item(&mut self, item: &'ast Item) {
+        let (kind, ident, vis, attrs) = match item {
+            Item::Struct(item) => ("struct", &item.ident, &item.vis, &item.attrs),
+            Item::Enum(item) => ("enum", &item.ident, &item.vis, &item.attrs),
+            Item::Trait(item) => ("trait", &item.ident, &item.vis, &item.attrs),
+            Item::Type(item) => ("type", &item.ident, &item.vis, &item.attrs),
+            Item::Fn(item) => ("function", &item.sig.ident, &item.vis, &item.attrs),
+            Item::Impl(item) if !is_test(&item.attrs) => return self.add_methods(item),
+            Item::Mod(item) if !is_test(&item.attrs) => {
+                return syn::visit::visit_item_mod(self, item)
+            }
+            _ => return,
+        };
+        if matches!(vis, Visibility::Public(_)) && !is_test(attrs) {
+            self.add(kind, ident, ident.to_string(), attrs);
+        }
+    }
+}
+
+/// Public items of a Rust source in the order they appear.
+pub fn public_items(source: &str) -> Result<Vec<PublicItem>> {
+    let file = syn::parse_file(source).map_err(|err| {
+        let start = err.span().start();
+        anyhow!("{}:{}: {err}", start.line, start.column + 1)
+    })?;
+    let mut collector = Collector::default();
+    collector.visit_file(&file);
+    Ok(collector.items)
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+/// Counts the documented public items of the Rust files among `files` and the ones with
+/// an `# Examples` section, listing the items that miss them.
+pub fn check_doc_coverage(
+    files: &[PathBuf],
+    workdir: &Path,
+    threshold: f64,
+    examples_threshold: f64,
+    console: &Console,
+) -> Result<()> {
+    let (mut total, mut documented, mut with_examples) = (0, 0, 0);
+    for file in files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|extension| extension == "rs"))
+    {
+        let path = file.strip_prefix(workdir).unwrap_or(file).display();
+        let source = fs::read_to_string(file).with_context(|| format!("failed to read {path}"))?;
+        let items = public_items(&source).with_context(|| format!("failed to parse {path}"))?;
+        for item in &items {
+            let missing = if !item.documented {
+                "undocumented"
+            } else if !item.has_examples && examples_threshold > 0.0 {
+                "no examples"
+            } else {
+                continue;
+            };
+            console_println!(
+                console,
+                "{path}:{}: {} {} `{}`",
+                item.line,
+                format!("[{missing}]").yellow().bold(),
+                item.kind,
+                item.name
+            );
+        }
+        total += items.len();
+        documented += items.iter().filter(|item| item.documented).count();
+        with_examples += items.iter().filter(|item| item.has_examples).count();
+    }
+
+    let coverage = percent(documented, total);
+    let examples_coverage = percent(with_examples, total);
+    console_println!(
+        console,
+        "{:>12} {documented}/{total} public items ({coverage:.1}%), \
+         {with_examples}/{total} with examples ({examples_coverage:.1}%)",
+        "Documented".cyan().bold()
+    );
+    if coverage < threshold {
+        bail!("{coverage:.1}% of public items are documented, below the threshold of {threshold}%");
+    }
+    if examples_coverage < examples_threshold {
+        bail!(
+            "{examples_coverage:.1}% of public items have examples, \
+             below the threshold of {examples_threshold}%"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn documented_items() {
+        let source = indoc! {"
+            /// Renames files.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// let renamer = Renamer::new();
+            /// ```
+            pub struct Renamer;
+
+            impl Renamer {
+                /// Creates a renamer.
+                pub fn new() -> Self {
+                    Self
+                }
+
+                pub fn run(&self) {}
+
+                fn helper(&self) {}
+            }
+
+            impl Default for Renamer {
+                fn default() -> Self {
+                    Self
+                }
+            }
+
+            pub(crate) fn internal() {}
+
+            #[cfg(test)]
+            mod tests {
+                pub fn undocumented_test_helper() {}
+            }
+        "};
+        let items: Vec<_> = public_items(source)
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.name, item.documented, item.has_examples))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("Renamer".to_string(), true, true),
+                ("Renamer::new".to_string(), true, false),
+                ("Renamer::run".to_string(), false, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod check;
+pub mod doc_coverage;
+pub mod rules;
+pub mod style;
+mod visitor;
@@ -0,0 +1,154 @@
+use anyhow::{bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+/// Abbreviations banned in names by default, `abbreviations` in the config adds more.
+const DEFAULT_ABBREVIATIONS: [&str; 28] = [
+    "arg", "args", "buf", "cfg", "cmd", "cnt", "ctx", "cur", "curr", "dir", "dst", "elem", "err",
+    "fl", "idx", "msg", "num", "obj", "pos", "prev", "ptr", "res", "ret", "src", "str", "tmp",
+    "val", "var",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Single-letter variables other than the allowed ones.
+    ShortNames,
+    /// Words of a name found in the dictionary of abbreviations.
+    Abbreviations,
+    LineLength,
+    /// Lines of a file, not counting tests.
+    FileLength,
+    FunctionLength,
+    /// Comments other than doc comments.
+    Comments,
+    /// Doc comments not in English.
+    DocLanguage,
+}
+
+const RULES: [Rule; 7] = [
+    Rule::ShortNames,
+    Rule::Abbreviations,
+    Rule::LineLength,
+    Rule::FileLength,
+    Rule::FunctionLength,
+    Rule::Comments,
+    Rule::DocLanguage,
+];
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ShortNames => "short-names",
+            Self::Abbreviations => "abbreviations",
+            Self::LineLength => "line-length",
+            Self::FileLength => "file-length",
+            Self::FunctionLength => "function-length",
+            Self::Comments => "comments",
+            Self::DocLanguage => "doc-language",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        RULES
+            .into_iter()
+            .find(|rule| rule.name() == name)
+            .with_context(|| {
+                format!(
+                    "unknown codestyle rule \"{name}\", available rules are: {}",
+                    RULES.map(|rule| rule.name()).join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Rule set of the `codestyle` command, the defaults are the rules of the course projects.
+pub struct Rules {
+    pub disabled: Vec<Rule>,
+    pub max_line_length: usize,
+    pub max_file_lines: usize,
+    pub max_function_lines: usize,
+    pub allowed_short_names: Vec<String>,
+    pub abbreviations: Vec<String>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            disabled: vec![],
+            max_line_length: 120,
+            max_file_lines: 120,
+            max_function_lines: 60,
+            allowed_short_names: vec!["i".to_string(), "j".to_string()],
+            abbreviations: DEFAULT_ABBREVIATIONS.map(String::from).to_vec(),
+        }
+    }
+}
+
+// Printed with the command before it runs, so the lists of names are left out.
+impl fmt::Debug for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rules")
+            .field("disabled", &self.disabled)
+            .field("max_line_length", &self.max_line_length)
+            .field("max_file_lines", &self.max_file_lines)
+            .field("max_function_lines", &self.max_function_lines)
+            .finish_non_exhaustive()
+    }
+}
+
+fn string_list(key: &str, value: &Value) -> Result<Vec<String>> {
+    value
+        .as_sequence()
+        .with_context(|| format!("{key} is not a list"))?
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .map(String::from)
+                .with_context(|| format!("{key} must contain only strings"))
+        })
+        .collect()
+}
+
+fn limit(key: &str, value: &Value) -> Result<usize> {
+    match value.as_u64() {
+        Some(limit) if limit > 0 => Ok(limit as usize),
+        _ => bail!("{key} must be a positive number"),
+    }
+}
+
+impl Rules {
+    /// Parses the parameters of `codestyle` in `.config.yml`, the missing ones keep the defaults.
+    pub fn from_yml(params: &Mapping) -> Result<Self> {
+        let mut rules = Self::default();
+        for (key, value) in params.iter() {
+            let key = key
+                .as_str()
+                .context("codestyle parameter name is not a string")?;
+            match key {
+                "disable" => {
+                    rules.disabled = string_list(key, value)?
+                        .iter()
+                        .map(|name| Rule::from_name(name))
+                        .collect::<Result<_>>()?
+                }
+                "max-line-length" => rules.max_line_length = limit(key, value)?,
+                "max-file-lines" => rules.max_file_lines = limit(key, value)?,
+                "max-function-lines" => rules.max_function_lines = limit(key, value)?,
+                "allowed-short-names" => rules.allowed_short_names = string_list(key, value)?,
+                "abbreviations" => rules.abbreviations.extend(string_list(key, value)?),
+                key => bail!("unknown codestyle parameter \"{key}\""),
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.disabled.contains(&rule)
+    }
+}
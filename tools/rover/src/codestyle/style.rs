@@ -0,0 +1,65 @@
+use super::{check::check_source, rules::Rules};
+use crate::{
+    console_println,
+    repository::{console::Console, problem::Problem},
+};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Checks the Rust files among `files`, printing the violations with paths relative to `workdir`.
+pub fn check_files(
+    files: &[PathBuf],
+    workdir: &Path,
+    rules: &Rules,
+    console: &Console,
+) -> Result<()> {
+    let mut checked = 0;
+    let mut found = 0;
+    for file in files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|extension| extension == "rs"))
+    {
+        let path = file.strip_prefix(workdir).unwrap_or(file).display();
+        let source = fs::read_to_string(file).with_context(|| format!("failed to read {path}"))?;
+        let violations =
+            check_source(&source, rules).with_context(|| format!("failed to parse {path}"))?;
+        for violation in &violations {
+            console_println!(
+                console,
+                "{path}:{}:{}: {} {}",
+                violation.line,
+                violation.column,
+                format!("[{}]", violation.rule).yellow().bold(),
+                violation.message
+            );
+        }
+        checked += 1;
+        found += violations.len();
+    }
+    if found > 0 {
+        bail!("found {found} codestyle violations, see above");
+    }
+    console_println!(
+        console,
+        "{:>12} {checked} files, no codestyle violations",
+        "Checked".green().bold()
+    );
+    Ok(())
+}
+
+/// Checks the solution files of the problem with the rules of its `codestyle` command,
+/// or with the default rules if the problem has none.
+pub fn check_problem_style(problem: &Problem) -> Result<()> {
+    let config = problem.config()?;
+    let default_rules = Rules::default();
+    check_files(
+        config.get_absolute_user_files(),
+        problem.path(),
+        config.get_codestyle_rules().unwrap_or(&default_rules),
+        &Console::default(),
+    )
+}
@@ -0,0 +1,285 @@
+use super::{
+    check::Violation,
+    rules::{Rule, Rules},
+};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use syn::{
+    spanned::Spanned, visit::Visit, Attribute, Block, Expr, Field, Ident, ImplItemFn, Item,
+    ItemConst, ItemEnum, ItemFn, ItemStatic, ItemStruct, ItemTrait, ItemType, Lit, Meta, PatIdent,
+    Signature, TraitItemFn, Variant,
+};
+
+/// Walks the AST of a file checking names, lengths of functions and doc comments.
+pub(super) struct Visitor<'a> {
+    rules: &'a Rules,
+    pub violations: Vec<Violation>,
+    /// Lines of the items marked with `#[test]` or `#[cfg(test)]`.
+    pub test_lines: usize,
+    in_test: bool,
+}
+
+/// Whether the `cfg` predicate has `test` in a positive position: bare or under `all` or `any`,
+/// like `all(test, feature = "slow")`. Code under `not(test)` is compiled outside of tests.
+fn cfg_is_test(predicate: TokenStream) -> bool {
+    let mut tokens = predicate.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let TokenTree::Ident(name) = token else {
+            continue;
+        };
+        let positive = match tokens.peek() {
+            // The arguments of `not` are skipped as the next token.
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                (name == "all" || name == "any") && cfg_is_test(group.stream())
+            }
+            // An option like `feature = "test"`.
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => false,
+            _ => name == "test",
+        };
+        if positive {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether the attributes mark a test, like `#[test]`, `#[tokio::test]` or `#[cfg(test)]` do.
+pub(super) fn is_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let path = attr.path();
+        if path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "test")
+        {
+            return true;
+        }
+        match &attr.meta {
+            Meta::List(list) => path.is_ident("cfg") && cfg_is_test(list.tokens.clone()),
+            _ => false,
+        }
+    })
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// Lowercase words of a name in `snake_case`, `SCREAMING_CASE` or `CamelCase`.
+fn words(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let symbols: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &symbol) in symbols.iter().enumerate() {
+            // A word starts at `Server` in both `FileServer` and `HTTPServer`.
+            let starts_word = i > 0
+                && symbol.is_uppercase()
+                && (!symbols[i - 1].is_uppercase()
+                    || symbols.get(i + 1).is_some_and(|next| next.is_lowercase()));
+            if starts_word {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(symbol.to_lowercase());
+        }
+        words.push(word);
+    }
+    words
+}
+
+impl<'a> Visitor<'a> {
+    pub fn new(rules: &'a Rules) -> Self {
+        Self {
+            rules,
+            violations: vec![],
+            test_lines: 0,
+            in_test: false,
+        }
+    }
+
+    fn report(&mut self, rule: Rule, span: Span, message: String) {
+        if self.rules.is_enabled(rule) {
+            let start = span.start();
+            self.violations.push(Violation {
+                line: start.line,
+                column: start.column + 1,
+                rule,
+                message,
+            });
+        }
+    }
+
+    fn check_abbreviations(&mut self, ident: &Ident) {
+        let name = ident.to_string();
+        for word in words(&name) {
+            if self.rules.abbreviations.contains(&word) {
+                self.report(
+                    Rule::Abbreviations,
+                    ident.span(),
+                    format!("`{name}` contains abbreviation \"{word}\", write the word in full"),
+                );
+            }
+        }
+    }
+
+    fn check_function(&mut self, signature: &Signature, block: &Block) {
+        self.check_abbreviations(&signature.ident);
+        let start = signature.fn_token.span.start().line;
+        let end = block.brace_token.span.close().end().line;
+        let lines = end - start + 1;
+        if lines > self.rules.max_function_lines {
+            self.report(
+                Rule::FunctionLength,
+                signature.ident.span(),
+                format!(
+                    "function `{}` is {lines} lines long, the limit is {}",
+                    signature.ident, self.rules.max_function_lines
+                ),
+            );
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Visitor<'_> {
+    fn visit_item(&mut self, item: &'ast Item) {
+        if self.in_test || !is_test(item_attrs(item)) {
+            return syn::visit::visit_item(self, item);
+        }
+        let span = item.span();
+        self.test_lines += span.end().line - span.start().line + 1;
+        self.in_test = true;
+        syn::visit::visit_item(self, item);
+        self.in_test = false;
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast Attribute) {
+        if let Meta::NameValue(doc) = &attr.meta {
+            if let (true, Expr::Lit(expr)) = (doc.path.is_ident("doc"), &doc.value) {
+                if let Lit::Str(text) = &expr.lit {
+                    let text = text.value();
+                    if text
+                        .chars()
+                        .any(|symbol| symbol.is_alphabetic() && !symbol.is_ascii())
+                    {
+                        self.report(
+                            Rule::DocLanguage,
+                            attr.span(),
+                            format!("doc comment is not in English: \"{}\"", text.trim()),
+                        );
+                    }
+                }
+            }
+        }
+        syn::visit::visit_attribute(self, attr);
+    }
+
+    fn visit_pat_ident(&mut self, pat: &'ast PatIdent) {
+        let name = pat.ident.to_string();
+        let name = name.trim_start_matches('_');
+        // Unit variants and constants in patterns, like `None`, are parsed as identifiers too.
+        if name.starts_with(|symbol: char| symbol.is_lowercase()) {
+            if name.chars().count() == 1
+                && !self
+                    .rules
+                    .allowed_short_names
+                    .iter()
+                    .any(|allowed| allowed == name)
+            {
+                self.report(
+                    Rule::ShortNames,
+                    pat.ident.span(),
+                    format!("single-letter variable `{name}`, give it a meaningful name"),
+                );
+            }
+            self.check_abbreviations(&pat.ident);
+        }
+        syn::visit::visit_pat_ident(self, pat);
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast ItemFn) {
+        self.check_function(&item.sig, &item.block);
+        syn::visit::visit_item_fn(self, item);
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast ImplItemFn) {
+        self.check_function(&item.sig, &item.block);
+        syn::visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast TraitItemFn) {
+        match &item.default {
+            Some(block) => self.check_function(&item.sig, block),
+            None => self.check_abbreviations(&item.sig.ident),
+        }
+        syn::visit::visit_trait_item_fn(self, item);
+    }
+
+    fn visit_item_struct(&mut self, item: &'ast ItemStruct) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_struct(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast ItemEnum) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_enum(self, item);
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast ItemTrait) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_trait(self, item);
+    }
+
+    fn visit_item_type(&mut self, item: &'ast ItemType) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_type(self, item);
+    }
+
+    fn visit_item_const(&mut self, item: &'ast ItemConst) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_const(self, item);
+    }
+
+    fn visit_item_static(&mut self, item: &'ast ItemStatic) {
+        self.check_abbreviations(&item.ident);
+        syn::visit::visit_item_static(self, item);
+    }
+
+    fn visit_field(&mut self, field: &'ast Field) {
+        if let Some(ident) = &field.ident {
+            self.check_abbreviations(ident);
+        }
+        syn::visit::visit_field(self, field);
+    }
+
+    fn visit_variant(&mut self, variant: &'ast Variant) {
+        self.check_abbreviations(&variant.ident);
+        syn::visit::visit_variant(self, variant);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_words() {
+        assert_eq!(words("file_cmd"), ["file", "cmd"]);
+        assert_eq!(words("_tmp"), ["tmp"]);
+        assert_eq!(words("MAX_BUF_SIZE"), ["max", "buf", "size"]);
+        assert_eq!(words("ParsedArgs"), ["parsed", "args"]);
+        assert_eq!(words("HTTPServer"), ["http", "server"]);
+        assert_eq!(words("Utf8Str"), ["utf8", "str"]);
+    }
+}
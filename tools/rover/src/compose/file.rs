@@ -1,5 +1,6 @@
-use anyhow::{bail, Context, Result};
-use std::{fs, path::Path};
+use super::source_map::SourceMap;
+use anyhow::{anyhow, Context, Result};
+use std::{fmt, fs, path::Path};
 
 #[allow(clippy::enum_variant_names)]
 enum TokenKind {
@@ -20,18 +21,80 @@ enum TokenProperty {
 struct Token {
     kind: TokenKind,
     properties: Vec<TokenProperty>,
+    column: usize,
 }
 
-fn parse_token(line: &str) -> Result<Option<Token>> {
-    let comment = match line.find("//") {
-        Some(pos) => &line[pos..],
+/// Error in compose markers, pointing to the 1-based line and column of the private file.
+#[derive(Debug)]
+pub struct MarkerError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl MarkerError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn at_token(line: usize, token: &Token, message: impl Into<String>) -> Self {
+        Self::new(line, token.column, message)
+    }
+}
+
+impl fmt::Display for MarkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for MarkerError {}
+
+/// Result of processing a single source file.
+pub struct ProcessedSource {
+    pub content: String,
+    /// `lines[i]` is the 1-based line of the private file that produced line `i + 1` of the public one.
+    pub lines: Vec<usize>,
+}
+
+impl ProcessedSource {
+    fn new() -> Self {
+        Self {
+            content: String::new(),
+            lines: vec![],
+        }
+    }
+
+    fn push_line(&mut self, parts: &[&str], origin: usize) {
+        for part in parts {
+            self.content.push_str(part);
+        }
+        self.content.push('\n');
+        self.lines.push(origin + 1);
+    }
+}
+
+fn column_of(line: &str, byte_pos: usize) -> usize {
+    line[..byte_pos].chars().count() + 1
+}
+
+fn parse_token(line: &str) -> Result<Option<Token>, MarkerError> {
+    let comment_pos = match line.find("//") {
+        Some(pos) => pos,
         None => return Ok(None),
     };
+    let comment = &line[comment_pos..];
 
-    let cmd = match comment.find("compose::") {
-        Some(pos) => &comment[pos + "compose::".len()..],
+    let cmd_pos = match comment.find("compose::") {
+        Some(pos) => comment_pos + pos,
         None => return Ok(None),
     };
+    let column = column_of(line, cmd_pos);
+    let cmd = &line[cmd_pos + "compose::".len()..];
 
     let kind = if cmd.starts_with("private") {
         TokenKind::Private
@@ -46,13 +109,21 @@ fn parse_token(line: &str) -> Result<Option<Token>> {
     } else if cmd.starts_with("end_uncomment") {
         TokenKind::EndUncomment
     } else {
-        bail!("unknown compose command: {}", cmd);
+        return Err(MarkerError::new(
+            0,
+            column,
+            format!("unknown compose command: {}", cmd),
+        ));
     };
 
     let properties_str = match cmd.find('(') {
         Some(pos) => {
             if !cmd.trim_end().ends_with(')') {
-                bail!("unclosed '('");
+                return Err(MarkerError::new(
+                    0,
+                    column_of(line, cmd_pos + "compose::".len() + pos),
+                    "unclosed '('",
+                ));
             }
             &cmd[pos + 1..cmd.trim_end().len() - 1]
         }
@@ -64,40 +135,59 @@ fn parse_token(line: &str) -> Result<Option<Token>> {
         match prop {
             "no_hint" => properties.push(TokenProperty::NoHint),
             "unimplemented" => properties.push(TokenProperty::Unimplemented),
-            s => bail!("unknown property: {}", s),
+            s => {
+                return Err(MarkerError::new(
+                    0,
+                    column,
+                    format!("unknown property: {}", s),
+                ))
+            }
         }
     }
 
-    Ok(Some(Token { kind, properties }))
+    Ok(Some(Token {
+        kind,
+        properties,
+        column,
+    }))
 }
 
-fn find_token(lines: &[&str], start: usize) -> Result<Option<(usize, Token)>> {
-    for (i, line) in lines[start..].iter().enumerate() {
-        let mb_token = parse_token(line)
-            .with_context(|| format!("failed to parse token on line {}", i + 1))?;
+fn find_token(lines: &[&str], start: usize) -> Result<Option<(usize, Token)>, MarkerError> {
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let mb_token = parse_token(line).map_err(|err| MarkerError { line: i + 1, ..err })?;
         if let Some(token) = mb_token {
-            return Ok(Some((i + start, token)));
+            return Ok(Some((i, token)));
         }
     }
     Ok(None)
 }
 
-fn process_source(src: String) -> Result<String> {
-    let mut dst = String::new();
+fn process_source(src: &str) -> Result<ProcessedSource, MarkerError> {
+    let mut dst = ProcessedSource::new();
 
     let lines = src.lines().collect::<Vec<_>>();
     let mut next_pos = 0;
     while let Some((begin, token)) = find_token(&lines, next_pos)? {
         let end = match token.kind {
-            TokenKind::EndPrivate => bail!("unpaired 'end_private' on line {}", begin + 1),
+            TokenKind::EndPrivate => {
+                return Err(MarkerError::at_token(
+                    begin + 1,
+                    &token,
+                    "unpaired 'end_private'",
+                ))
+            }
             TokenKind::Private => begin + 2,
             TokenKind::BeginPrivate => {
                 let mut pos = begin + 1;
                 let mut mb_end: Option<usize> = None;
-                while let Some((k, token)) = find_token(&lines, pos)? {
-                    match token.kind {
+                while let Some((k, inner)) = find_token(&lines, pos)? {
+                    match inner.kind {
                         TokenKind::BeginPrivate => {
-                            bail!("nested 'begin_private' on line {}", k + 1)
+                            return Err(MarkerError::at_token(
+                                k + 1,
+                                &inner,
+                                "nested 'begin_private'",
+                            ))
                         }
                         TokenKind::Private => pos = k + 1,
                         TokenKind::EndPrivate => {
@@ -107,24 +197,44 @@ fn process_source(src: String) -> Result<String> {
                         TokenKind::BeginUncomment
                         | TokenKind::Uncomment
                         | TokenKind::EndUncomment => {
-                            bail!("nested uncomment-type token on line {}", k + 1);
+                            return Err(MarkerError::at_token(
+                                k + 1,
+                                &inner,
+                                "nested uncomment-type token",
+                            ))
                         }
                     }
                 }
                 match mb_end {
                     Some(end) => end + 1,
-                    None => bail!("unclosed 'begin_private' on line {}", begin + 1),
+                    None => {
+                        return Err(MarkerError::at_token(
+                            begin + 1,
+                            &token,
+                            "unclosed 'begin_private'",
+                        ))
+                    }
                 }
             }
             TokenKind::Uncomment => begin + 2,
-            TokenKind::EndUncomment => bail!("unpaired 'end_uncomment' on line {}", begin + 1),
+            TokenKind::EndUncomment => {
+                return Err(MarkerError::at_token(
+                    begin + 1,
+                    &token,
+                    "unpaired 'end_uncomment'",
+                ))
+            }
             TokenKind::BeginUncomment => {
                 let mut pos = begin + 1;
                 let mut mb_end: Option<usize> = None;
-                while let Some((k, token)) = find_token(&lines, pos)? {
-                    match token.kind {
+                while let Some((k, inner)) = find_token(&lines, pos)? {
+                    match inner.kind {
                         TokenKind::BeginUncomment => {
-                            bail!("nested 'begin_uncomment' on line {}", k + 1)
+                            return Err(MarkerError::at_token(
+                                k + 1,
+                                &inner,
+                                "nested 'begin_uncomment'",
+                            ))
                         }
                         TokenKind::Uncomment => pos = k + 1,
                         TokenKind::EndUncomment => {
@@ -132,21 +242,36 @@ fn process_source(src: String) -> Result<String> {
                             break;
                         }
                         TokenKind::BeginPrivate | TokenKind::Private | TokenKind::EndPrivate => {
-                            bail!("nested 'private'-type token on line {}", k + 1)
+                            return Err(MarkerError::at_token(
+                                k + 1,
+                                &inner,
+                                "nested 'private'-type token",
+                            ))
                         }
                     }
                 }
                 match mb_end {
                     Some(end) => end + 1,
-                    None => bail!("unclosed 'begin_uncomment' on line {}", begin + 1),
+                    None => {
+                        return Err(MarkerError::at_token(
+                            begin + 1,
+                            &token,
+                            "unclosed 'begin_uncomment'",
+                        ))
+                    }
                 }
             }
         };
+        if end > lines.len() {
+            return Err(MarkerError::at_token(
+                begin + 1,
+                &token,
+                "marker is not followed by a line",
+            ));
+        }
 
-        #[allow(clippy::needless_range_loop)]
-        for i in next_pos..begin {
-            dst += lines[i];
-            dst += "\n";
+        for (i, line) in lines.iter().enumerate().take(begin).skip(next_pos) {
+            dst.push_line(&[line], i);
         }
 
         if matches!(token.kind, TokenKind::BeginPrivate | TokenKind::Private) {
@@ -163,61 +288,91 @@ fn process_source(src: String) -> Result<String> {
                     next_pos = end;
                 }
             } else {
-                let mut insert_line = |line: &str| {
-                    for c in lines[begin].chars() {
-                        if c.is_whitespace() {
-                            dst.push(c);
-                        } else {
-                            break;
-                        }
-                    }
-                    dst.push_str(line);
-                };
+                let line = lines[begin];
+                let indent = &line[..line.len() - line.trim_start().len()];
 
-                insert_line("// TODO: your code goes here.\n");
+                dst.push_line(&[indent, "// TODO: your code goes here."], begin);
                 if unimpl {
-                    insert_line("unimplemented!()\n");
+                    dst.push_line(&[indent, "unimplemented!()"], begin);
                 }
 
                 next_pos = end;
             }
         } else if matches!(token.kind, TokenKind::BeginUncomment | TokenKind::Uncomment) {
             let has_end_uncomment = matches!(token.kind, TokenKind::BeginUncomment) as usize;
-            for i in begin + 1..end - has_end_uncomment {
-                let line = lines[i];
-                let (indent, content) = line.split_once("// ").context(format!(
-                    "No comment found in uncomment block on line {}",
-                    i + 1
-                ))?;
-                dst.push_str(indent);
-                dst.push_str(content);
-                dst.push_str("\n");
+            for (i, line) in lines
+                .iter()
+                .enumerate()
+                .take(end - has_end_uncomment)
+                .skip(begin + 1)
+            {
+                let (indent, content) = line.split_once("// ").ok_or_else(|| {
+                    let column = line.len() - line.trim_start().len() + 1;
+                    MarkerError::new(i + 1, column, "no comment found in uncomment block")
+                })?;
+                dst.push_line(&[indent, content], i);
             }
 
             next_pos = end;
         }
     }
 
-    for line in &lines[next_pos..] {
-        dst += line;
-        dst += "\n";
+    for (i, line) in lines.iter().enumerate().skip(next_pos) {
+        dst.push_line(&[line], i);
     }
 
     Ok(dst)
 }
 
-pub fn process_file(input: &Path, output: &Path) -> Result<()> {
+pub fn process_file(input: &Path, output: &Path, source_map: &mut SourceMap) -> Result<()> {
     let out_dir = output.parent().unwrap();
     fs::create_dir_all(out_dir).context("failed to create directory")?;
     if input.to_str().map(|s| s.ends_with(".rs")).unwrap_or(false) {
         let content = fs::read_to_string(input)
             .with_context(|| format!("failed to read file {:?}", input))?;
-        let new_content = process_source(content)
-            .with_context(|| format!("failed to process file {:?}", input))?;
-        fs::write(output, new_content).with_context(|| format!("failed to write file {:?}", input))
+        let processed =
+            process_source(&content).map_err(|err| anyhow!("{}:{}", input.display(), err))?;
+        fs::write(output, processed.content)
+            .with_context(|| format!("failed to write file {:?}", input))?;
+        source_map.add_file(input, output, processed.lines);
+        Ok(())
     } else {
         fs::copy(input, output)
             .map(|_| ())
             .with_context(|| format!("failed to copy {:?} to {:?}", input, output))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unclosed_marker_position() {
+        let src = "fn a() {\n    // compose::private\n    secret();\n}\n// compose::begin_private\nfn b() {}\n";
+        let err = process_source(src).err().unwrap();
+        assert_eq!(err.to_string(), "5:4: unclosed 'begin_private'");
+    }
+
+    #[test]
+    fn line_mapping() {
+        let src = "\
+fn a() -> u32 {
+    // compose::begin_private(unimplemented)
+    let x = 1;
+    x
+    // compose::end_private
+}
+// compose::begin_uncomment
+// fn b() {}
+// compose::end_uncomment
+fn c() {}
+";
+        let processed = process_source(src).unwrap();
+        assert_eq!(
+            processed.content,
+            "fn a() -> u32 {\n    // TODO: your code goes here.\n    unimplemented!()\n}\nfn b() {}\nfn c() {}\n"
+        );
+        assert_eq!(processed.lines, [1, 2, 2, 6, 8, 10]);
+    }
+}
@@ -6,3 +6,4 @@ mod process;
 mod prune;
 pub mod run_compose;
 mod skip;
+mod source_map;
@@ -1,8 +1,13 @@
-use super::{config::Config, dir::process_dir, file::process_file};
+use super::{config::Config, dir::process_dir, file::process_file, source_map::SourceMap};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
 
-pub fn process(input: &Path, output: &Path, config: &Config) -> Result<()> {
+pub fn process(
+    input: &Path,
+    output: &Path,
+    config: &Config,
+    source_map: &mut SourceMap,
+) -> Result<()> {
     let duplicate = |path: PathBuf| -> (PathBuf, PathBuf) { (path.clone(), path) };
     let to_process = std::iter::empty()
         .chain(
@@ -22,9 +27,9 @@ pub fn process(input: &Path, output: &Path, config: &Config) -> Result<()> {
         let input = input.join(&from);
         let output = output.join(&to);
         if input.is_dir() {
-            process_dir(&input, &output)?;
+            process_dir(&input, &output, source_map)?;
         } else {
-            process_file(&input, &output)?;
+            process_file(&input, &output, source_map)?;
         }
     }
     Ok(())
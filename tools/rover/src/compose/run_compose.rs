@@ -1,17 +1,27 @@
-use super::{cargo_root::cargo_root, process::process, prune::prune, skip::skip};
+use super::{
+    cargo_root::cargo_root, process::process, prune::prune, skip::skip, source_map::SourceMap,
+};
 use crate::repository::repo::Repository;
 use anyhow::{Context, Result};
 use std::path::Path;
 
-pub fn run_compose(input: &Path, output: &Path) -> Result<()> {
+pub fn run_compose(input: &Path, output: &Path, source_map_path: Option<&Path>) -> Result<()> {
     let repository = Repository::from_path(input)?;
     let config = repository.compose_config()?;
     let input = repository.get_path().to_path_buf();
     let output = output
         .canonicalize()
         .context("could not canonicalize output path")?;
+    let mut source_map = SourceMap::new(&input, &output);
     prune(&output, &config)?;
-    process(&input, &output, &config)?;
+    process(&input, &output, &config, &mut source_map)?;
     cargo_root(&output, &config)?;
-    skip(&output, &config)
+    skip(&output, &config)?;
+    match source_map_path {
+        Some(path) => {
+            source_map.retain_existing();
+            source_map.write(path)
+        }
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SOURCE_MAP_VERSION: u32 = 1;
+
+#[derive(Serialize, Debug)]
+struct FileMap {
+    public: PathBuf,
+    private: PathBuf,
+    /// `lines[i]` is the 1-based line in the private file for the line `i + 1` of the public file.
+    lines: Vec<usize>,
+}
+
+/// Mapping between lines of public templates and private solutions built during compose.
+#[derive(Serialize, Debug)]
+pub struct SourceMap {
+    version: u32,
+    #[serde(skip)]
+    input: PathBuf,
+    #[serde(skip)]
+    output: PathBuf,
+    files: Vec<FileMap>,
+}
+
+impl SourceMap {
+    pub fn new(input: &Path, output: &Path) -> Self {
+        Self {
+            version: SOURCE_MAP_VERSION,
+            input: input.to_path_buf(),
+            output: output.to_path_buf(),
+            files: vec![],
+        }
+    }
+
+    pub fn add_file(&mut self, input: &Path, output: &Path, lines: Vec<usize>) {
+        let relative = |path: &Path, root: &Path| {
+            path.strip_prefix(root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.to_path_buf())
+        };
+        self.files.push(FileMap {
+            public: relative(output, &self.output),
+            private: relative(input, &self.input),
+            lines,
+        });
+    }
+
+    /// Drops files that were removed from the output after processing, e.g. by `skip-entries`.
+    pub fn retain_existing(&mut self) {
+        let output = &self.output;
+        self.files.retain(|file| output.join(&file.public).exists());
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(self).context("failed to serialize source map")?;
+        fs::write(path, content).with_context(|| format!("failed to write source map to {path:?}"))
+    }
+}
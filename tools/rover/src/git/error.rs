@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("failed to open git repository at {path:?}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: git2::Error,
+    },
+    #[error("repository at {0:?} has no working directory")]
+    BareRepository(PathBuf),
+    #[error("path {0:?} is outside of the repository working directory")]
+    OutsideWorkdir(PathBuf),
+    #[error(
+        "git user identity is not configured, \
+         set it with `git config user.name` and `git config user.email`"
+    )]
+    NoSignature(#[source] git2::Error),
+    #[error("branch \"{0}\" was found neither locally nor on the remote")]
+    BranchNotFound(String),
+    #[error("cannot checkout branch \"{branch}\"")]
+    Checkout {
+        branch: String,
+        #[source]
+        source: git2::Error,
+    },
+    #[error("remote \"{0}\" is not configured")]
+    RemoteNotFound(String),
+    #[error("remote \"{0}\" has no default branch")]
+    NoDefaultBranch(String),
+    #[error("authentication to remote \"{remote}\" failed")]
+    Authentication {
+        remote: String,
+        #[source]
+        source: git2::Error,
+    },
+    #[error("remote \"{remote}\" rejected {reference}: {reason}")]
+    PushRejected {
+        remote: String,
+        reference: String,
+        reason: String,
+    },
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+}
@@ -0,0 +1,777 @@
+mod error;
+
+pub use error::GitError;
+
+use git2::{
+    build::CheckoutBuilder, BranchType, Cred, CredentialType, Diff, DiffStatsFormat, Direction,
+    ErrorClass, ErrorCode, FetchOptions, Index, IndexAddOption, IndexEntry, IndexTime, Oid,
+    PushOptions, RemoteCallbacks, Repository, Signature, Status, StatusOptions,
+};
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub type GitResult<T> = std::result::Result<T, GitError>;
+
+pub const DEFAULT_REMOTE: &str = "origin";
+const DIFF_STAT_WIDTH: usize = 80;
+const MAX_LISTED_COMMITS: usize = 20;
+
+/// Branches of a remote as seen by `git ls-remote`.
+pub struct RemoteHeads {
+    pub default_branch: Option<String>,
+    pub heads: Vec<(String, Oid)>,
+}
+
+impl RemoteHeads {
+    pub fn get(&self, branch: &str) -> Option<Oid> {
+        let name = format!("refs/heads/{branch}");
+        self.heads
+            .iter()
+            .find(|(head, _)| *head == name)
+            .map(|(_, oid)| *oid)
+    }
+}
+
+/// Git operations used by rover, backed by libgit2.
+pub struct GitRepository {
+    repo: Repository,
+}
+
+impl GitRepository {
+    pub fn open(path: &Path) -> GitResult<Self> {
+        let repo = Repository::discover(path).map_err(|source| GitError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if repo.is_bare() {
+            return Err(GitError::BareRepository(path.to_path_buf()));
+        }
+        Ok(Self { repo })
+    }
+
+    pub fn workdir(&self) -> &Path {
+        self.repo.workdir().expect("repository is not bare")
+    }
+
+    fn relative_to_workdir(&self, path: &Path) -> GitResult<PathBuf> {
+        if path.is_relative() {
+            return Ok(path.to_path_buf());
+        }
+        let workdir = self
+            .workdir()
+            .canonicalize()
+            .map_err(|_| GitError::OutsideWorkdir(path.to_path_buf()))?;
+        path.strip_prefix(&workdir)
+            .map(Path::to_path_buf)
+            .map_err(|_| GitError::OutsideWorkdir(path.to_path_buf()))
+    }
+
+    /// Stages all changes under `path`, including new and deleted files, like `git add PATH`.
+    pub fn add(&self, path: &Path) -> GitResult<()> {
+        let pathspec = self.relative_to_workdir(path)?;
+        let mut index = self.repo.index()?;
+        index.add_all([&pathspec], IndexAddOption::DEFAULT, None)?;
+        index.update_all([&pathspec], None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    fn signature(&self) -> GitResult<Signature<'static>> {
+        self.repo.signature().map_err(GitError::NoSignature)
+    }
+
+    /// Commits the index on top of `HEAD`, even if there are no changes, like `git commit --allow-empty`.
+    pub fn commit(&self, message: &str) -> GitResult<Oid> {
+        let signature = self.signature()?;
+        let tree = self.repo.find_tree(self.repo.index()?.write_tree()?)?;
+        let parent = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(err) if err.code() == ErrorCode::UnbornBranch => None,
+            Err(err) => return Err(err.into()),
+        };
+        let parents: Vec<_> = parent.iter().collect();
+        Ok(self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )?)
+    }
+
+    /// Creates a commit object without updating any reference, like `git commit-tree`.
+    pub fn commit_tree(&self, tree: Oid, parent: Oid, message: &str) -> GitResult<Oid> {
+        let signature = self.signature()?;
+        let tree = self.repo.find_tree(tree)?;
+        let parent = self.repo.find_commit(parent)?;
+        Ok(self
+            .repo
+            .commit(None, &signature, &signature, message, &tree, &[&parent])?)
+    }
+
+    /// Switches to the local branch `name`, creating it from `origin/NAME` if needed, like `git checkout NAME`.
+    pub fn checkout_branch(&self, name: &str) -> GitResult<()> {
+        let branch = match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                let remote_name = format!("{DEFAULT_REMOTE}/{name}");
+                let remote = match self.repo.find_branch(&remote_name, BranchType::Remote) {
+                    Ok(remote) => remote,
+                    Err(err) if err.code() == ErrorCode::NotFound => {
+                        return Err(GitError::BranchNotFound(name.to_string()))
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                let mut branch = self
+                    .repo
+                    .branch(name, &remote.get().peel_to_commit()?, false)?;
+                branch.set_upstream(Some(&remote_name))?;
+                branch
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let reference = branch.into_reference();
+        let refname = reference.name().expect("branch name is utf-8").to_string();
+        let checkout = || -> Result<(), git2::Error> {
+            self.repo.checkout_tree(
+                reference.peel_to_tree()?.as_object(),
+                Some(CheckoutBuilder::new().safe()),
+            )?;
+            self.repo.set_head(&refname)
+        };
+        checkout().map_err(|source| GitError::Checkout {
+            branch: name.to_string(),
+            source,
+        })
+    }
+
+    /// Paths of all files that differ from `HEAD`, including untracked ones, relative to the working directory.
+    pub fn changed_paths(&self) -> GitResult<Vec<PathBuf>> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = self.repo.statuses(Some(&mut options))?;
+        Ok(statuses
+            .iter()
+            .filter(|entry| entry.status() != Status::CURRENT)
+            .filter_map(|entry| entry.path().map(PathBuf::from))
+            .collect())
+    }
+
+    fn diff_stat(diff: &Diff) -> GitResult<String> {
+        let stats = diff.stats()?;
+        if stats.files_changed() == 0 {
+            return Ok(String::new());
+        }
+        let buf = stats.to_buf(DiffStatsFormat::FULL, DIFF_STAT_WIDTH)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    /// Commits reachable from `from` but not from `hidden`, newest first, with their summaries.
+    fn commits_between(&self, from: Oid, hidden: Option<Oid>) -> GitResult<Vec<(Oid, String)>> {
+        let mut walk = self.repo.revwalk()?;
+        walk.push(from)?;
+        if let Some(hidden) = hidden {
+            walk.hide(hidden)?;
+        }
+        walk.map(|oid| {
+            let oid = oid?;
+            let commit = self.repo.find_commit(oid)?;
+            Ok((oid, commit.summary().unwrap_or_default().to_string()))
+        })
+        .collect()
+    }
+
+    /// What pushing `commit` over the remote branch at `remote` changes, like
+    /// `git log --oneline remote..commit` followed by `git diff --stat remote commit`.
+    /// `remote` is `None` if the branch doesn't exist on the remote yet.
+    pub fn push_summary(&self, remote: Option<Oid>, commit: Oid) -> GitResult<String> {
+        let mut summary = String::new();
+        let ahead = self.commits_between(commit, remote)?;
+        summary.push_str(&format!("{} new commit(s):\n", ahead.len()));
+        for (oid, message) in ahead.iter().take(MAX_LISTED_COMMITS) {
+            summary.push_str(&format!("  {} {message}\n", &oid.to_string()[..12]));
+        }
+        if ahead.len() > MAX_LISTED_COMMITS {
+            summary.push_str(&format!(
+                "  ... and {} more\n",
+                ahead.len() - MAX_LISTED_COMMITS
+            ));
+        }
+        if let Some(remote) = remote {
+            let replaced = self.commits_between(remote, Some(commit))?.len();
+            if replaced > 0 {
+                summary.push_str(&format!(
+                    "{replaced} commit(s) on the remote will be overwritten\n"
+                ));
+            }
+        }
+        let old = match remote {
+            Some(remote) => Some(self.repo.find_commit(remote)?.tree()?),
+            None => None,
+        };
+        let new = self.repo.find_commit(commit)?.tree()?;
+        let diff = self
+            .repo
+            .diff_tree_to_tree(old.as_ref(), Some(&new), None)?;
+        match Self::diff_stat(&diff)? {
+            stat if stat.is_empty() => summary.push_str("no changes in files\n"),
+            stat => summary.push_str(&stat),
+        }
+        Ok(summary)
+    }
+
+    /// Builds a tree from the commit `base` with `files` replaced by their contents in the working directory.
+    /// Entries of `base` for which `replaced` holds are dropped first, so the ones missing from
+    /// `files` are deleted.
+    pub fn tree_with_files(
+        &self,
+        base: Oid,
+        files: &[PathBuf],
+        replaced: impl Fn(&Path) -> bool,
+    ) -> GitResult<Oid> {
+        let mut index = Index::new()?;
+        index.read_tree(&self.repo.find_commit(base)?.tree()?)?;
+        let stale: Vec<_> = index
+            .iter()
+            .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+            .filter(|path| replaced(path))
+            .collect();
+        for path in stale {
+            index.remove_path(&path)?;
+        }
+        for file in files {
+            let relative = self.relative_to_workdir(file)?;
+            let absolute = self.workdir().join(&relative);
+            let blob = self.repo.blob_path(&absolute)?;
+            let metadata = fs::metadata(&absolute)
+                .map_err(|err| git2::Error::from_str(&format!("{absolute:?}: {err}")))?;
+            let path = relative
+                .to_str()
+                .ok_or_else(|| git2::Error::from_str("non-utf-8 path"))?
+                .replace('\\', "/")
+                .into_bytes();
+            index.add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: file_mode(&metadata),
+                uid: 0,
+                gid: 0,
+                file_size: metadata.len() as u32,
+                id: blob,
+                flags: path.len().min(0xfff) as u16,
+                flags_extended: 0,
+                path,
+            })?;
+        }
+        Ok(index.write_tree_to(&self.repo)?)
+    }
+
+    fn find_remote(&self, name: &str) -> GitResult<git2::Remote<'_>> {
+        let remote = self
+            .repo
+            .find_remote(name)
+            .map_err(|err| match err.code() {
+                ErrorCode::NotFound => GitError::RemoteNotFound(name.to_string()),
+                _ => err.into(),
+            })?;
+        // libgit2 doesn't resolve relative paths in remote urls, unlike git itself.
+        let relative_url = remote
+            .url()
+            .filter(|url| !url.contains(':') && Path::new(url).is_relative())
+            .map(PathBuf::from);
+        match relative_url {
+            Some(url) => {
+                let url = self.workdir().join(url);
+                let url = url
+                    .to_str()
+                    .ok_or_else(|| GitError::RemoteNotFound(name.to_string()))?;
+                Ok(self.repo.remote_anonymous(url)?)
+            }
+            None => Ok(remote),
+        }
+    }
+
+    fn network_error(remote: &str, err: git2::Error) -> GitError {
+        if err.code() == ErrorCode::Auth
+            || (matches!(err.class(), ErrorClass::Ssh | ErrorClass::Http)
+                && err.message().contains("authentication"))
+        {
+            GitError::Authentication {
+                remote: remote.to_string(),
+                source: err,
+            }
+        } else {
+            err.into()
+        }
+    }
+
+    /// Lists branches of the remote, like `git ls-remote --heads`.
+    pub fn list_remote(&self, remote_name: &str) -> GitResult<RemoteHeads> {
+        let mut remote = self.find_remote(remote_name)?;
+        let connection = remote
+            .connect_auth(Direction::Fetch, Some(remote_callbacks()), None)
+            .map_err(|err| Self::network_error(remote_name, err))?;
+        let default_branch = connection
+            .default_branch()
+            .ok()
+            .and_then(|name| name.as_str().map(str::to_string));
+        let heads = connection
+            .list()?
+            .iter()
+            .filter(|head| head.name().starts_with("refs/heads/"))
+            .map(|head| (head.name().to_string(), head.oid()))
+            .collect();
+        Ok(RemoteHeads {
+            default_branch,
+            heads,
+        })
+    }
+
+    /// Fetches `refs/heads/BRANCH` into `refs/remotes/REMOTE/BRANCH` and returns the fetched commit.
+    pub fn fetch_branch(&self, remote_name: &str, branch: &str) -> GitResult<Oid> {
+        let mut remote = self.find_remote(remote_name)?;
+        let refspec = format!("+refs/heads/{branch}:refs/remotes/{remote_name}/{branch}");
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(remote_callbacks());
+        remote
+            .fetch(&[&refspec], Some(&mut options), None)
+            .map_err(|err| Self::network_error(remote_name, err))?;
+        Ok(self
+            .repo
+            .refname_to_id(&format!("refs/remotes/{remote_name}/{branch}"))?)
+    }
+
+    /// Fetches all branches of the remote using its configured refspecs, like `git fetch REMOTE`.
+    pub fn fetch(&self, remote_name: &str) -> GitResult<()> {
+        let mut remote = self.find_remote(remote_name)?;
+        // Refspecs are passed explicitly, since anonymous remotes for relative urls have none.
+        let refspecs: Vec<_> = self
+            .repo
+            .find_remote(remote_name)?
+            .fetch_refspecs()?
+            .iter()
+            .flatten()
+            .map(str::to_string)
+            .collect();
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(remote_callbacks());
+        remote
+            .fetch(&refspecs, Some(&mut options), None)
+            .map_err(|err| Self::network_error(remote_name, err))
+    }
+
+    /// The commit of `refs/remotes/REMOTE/BRANCH` as of the last fetch, if there's such a branch.
+    pub fn remote_tracking_commit(
+        &self,
+        remote_name: &str,
+        branch: &str,
+    ) -> GitResult<Option<Oid>> {
+        match self
+            .repo
+            .refname_to_id(&format!("refs/remotes/{remote_name}/{branch}"))
+        {
+            Ok(oid) => Ok(Some(oid)),
+            Err(err) if err.code() == ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Checks whether the file in the working directory has the same content as in `commit`.
+    pub fn file_matches_commit(&self, commit: Oid, path: &Path) -> GitResult<bool> {
+        let relative = self.relative_to_workdir(path)?;
+        let tree = self.repo.find_commit(commit)?.tree()?;
+        let entry = match tree.get_path(&relative) {
+            Ok(entry) => entry,
+            Err(err) if err.code() == ErrorCode::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+        let absolute = self.workdir().join(&relative);
+        if !absolute.is_file() {
+            return Ok(false);
+        }
+        Ok(Oid::hash_file(git2::ObjectType::Blob, &absolute)? == entry.id())
+    }
+
+    /// Fetches the branch `HEAD` of the remote points to and returns the fetched commit.
+    pub fn fetch_default_branch(&self, remote_name: &str) -> GitResult<Oid> {
+        let default_branch = self
+            .list_remote(remote_name)?
+            .default_branch
+            .ok_or_else(|| GitError::NoDefaultBranch(remote_name.to_string()))?;
+        let branch = default_branch
+            .strip_prefix("refs/heads/")
+            .unwrap_or(&default_branch);
+        self.fetch_branch(remote_name, branch)
+    }
+
+    /// Pushes `refspec` to the remote. Prefix the refspec with `+` to force the push.
+    pub fn push(&self, remote_name: &str, refspec: &str) -> GitResult<()> {
+        let mut remote = self.find_remote(remote_name)?;
+        let reference = refspec.rsplit(':').next().unwrap_or(refspec).to_string();
+        let rejected = RefCell::new(None);
+        let result = {
+            let mut callbacks = remote_callbacks();
+            callbacks.push_update_reference(|reference, status| {
+                if let Some(reason) = status {
+                    rejected.replace(Some((reference.to_string(), reason.to_string())));
+                }
+                Ok(())
+            });
+            let mut options = PushOptions::new();
+            options.remote_callbacks(callbacks);
+            remote.push(&[refspec], Some(&mut options))
+        };
+        result.map_err(|err| match err.code() {
+            ErrorCode::NotFastForward => GitError::PushRejected {
+                remote: remote_name.to_string(),
+                reference,
+                reason: "non-fast-forward".to_string(),
+            },
+            _ => Self::network_error(remote_name, err),
+        })?;
+        match rejected.into_inner() {
+            Some((reference, reason)) => Err(GitError::PushRejected {
+                remote: remote_name.to_string(),
+                reference,
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    if metadata.permissions().mode() & 0o111 != 0 {
+        0o100755
+    } else {
+        0o100644
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o100644
+}
+
+/// Callbacks trying the ssh agent, then git credential helpers, then the default credentials.
+fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut tried = CredentialType::empty();
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) && !tried.contains(CredentialType::SSH_KEY) {
+            tried |= CredentialType::SSH_KEY;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !tried.contains(CredentialType::USER_PASS_PLAINTEXT)
+        {
+            tried |= CredentialType::USER_PASS_PLAINTEXT;
+            let config = git2::Config::open_default()?;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !tried.contains(CredentialType::DEFAULT) {
+            tried |= CredentialType::DEFAULT;
+            return Cred::default();
+        }
+        Err(git2::Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Net,
+            "no suitable credentials",
+        ))
+    });
+    callbacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct Setup {
+        _dir: TempDir,
+        remote: PathBuf,
+        work: PathBuf,
+    }
+
+    fn init_work_repo(path: &Path, remote: &Path) -> GitRepository {
+        let repo = Repository::init(path).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Rover").unwrap();
+        config.set_str("user.email", "rover@example.com").unwrap();
+        // Work repositories and the remote are siblings, relative url checks how it's resolved.
+        let url = Path::new("..").join(remote.file_name().unwrap());
+        repo.remote("origin", url.to_str().unwrap()).unwrap();
+        GitRepository { repo }
+    }
+
+    fn setup() -> (Setup, GitRepository) {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        Repository::init_bare(&remote)
+            .unwrap()
+            .set_head("refs/heads/main")
+            .unwrap();
+        let work = dir.path().join("work");
+        let repo = init_work_repo(&work, &remote);
+        (
+            Setup {
+                _dir: dir,
+                remote,
+                work,
+            },
+            repo,
+        )
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn remote_file(remote: &Path, branch: &str, path: &str) -> Option<String> {
+        let repo = Repository::open_bare(remote).unwrap();
+        let reference = repo.find_reference(&format!("refs/heads/{branch}")).ok()?;
+        let tree = reference.peel_to_tree().unwrap();
+        let entry = tree.get_path(Path::new(path)).ok()?;
+        let blob = repo.find_blob(entry.id()).unwrap();
+        Some(String::from_utf8(blob.content().to_vec()).unwrap())
+    }
+
+    fn commit_and_push(repo: &GitRepository, work: &Path, file: &str, content: &str) -> Oid {
+        write(&work.join(file), content);
+        repo.add(Path::new(file)).unwrap();
+        let oid = repo.commit("commit").unwrap();
+        repo.push("origin", "HEAD:refs/heads/main").unwrap();
+        oid
+    }
+
+    #[test]
+    fn add_commit_and_push() {
+        let (setup, repo) = setup();
+        write(
+            &setup.work.join("problems/g/t/src/lib.rs"),
+            "fn main() {}\n",
+        );
+        write(&setup.work.join("other.txt"), "other\n");
+        repo.add(Path::new("problems/g/t")).unwrap();
+        assert_eq!(repo.changed_paths().unwrap().len(), 2);
+
+        let first = repo.commit("first").unwrap();
+        assert!(repo
+            .push_summary(None, first)
+            .unwrap()
+            .contains("problems/g/t/src/lib.rs"));
+        assert_eq!(
+            repo.changed_paths().unwrap(),
+            vec![PathBuf::from("other.txt")]
+        );
+        repo.push("origin", "HEAD:refs/heads/g/t").unwrap();
+        assert_eq!(
+            remote_file(&setup.remote, "g/t", "problems/g/t/src/lib.rs").as_deref(),
+            Some("fn main() {}\n")
+        );
+        assert_eq!(remote_file(&setup.remote, "g/t", "other.txt"), None);
+    }
+
+    #[test]
+    fn commit_allows_empty() {
+        let (_setup, repo) = setup();
+        let first = repo.commit("first").unwrap();
+        let second = repo.commit("second").unwrap();
+        assert_ne!(first, second);
+        assert!(repo
+            .push_summary(Some(first), second)
+            .unwrap()
+            .ends_with("no changes in files\n"));
+    }
+
+    #[test]
+    fn add_stages_deletions() {
+        let (setup, repo) = setup();
+        write(&setup.work.join("dir/a.rs"), "a\n");
+        repo.add(Path::new("dir")).unwrap();
+        let added = repo.commit("add").unwrap();
+        fs::remove_file(setup.work.join("dir/a.rs")).unwrap();
+        repo.add(Path::new("dir")).unwrap();
+        let removed = repo.commit("remove").unwrap();
+        assert!(repo
+            .push_summary(Some(added), removed)
+            .unwrap()
+            .contains("dir/a.rs"));
+    }
+
+    #[test]
+    fn add_rejects_paths_outside_workdir() {
+        let (_setup, repo) = setup();
+        let outside = TempDir::new().unwrap();
+        let err = repo
+            .add(&outside.path().canonicalize().unwrap())
+            .unwrap_err();
+        assert!(matches!(err, GitError::OutsideWorkdir(_)));
+    }
+
+    #[test]
+    fn checkout_branch_from_remote() {
+        let (setup, repo) = setup();
+        commit_and_push(&repo, &setup.work, "a.txt", "main\n");
+        write(&setup.work.join("a.txt"), "problem\n");
+        repo.add(Path::new("a.txt")).unwrap();
+        repo.commit("problem").unwrap();
+        repo.push("origin", "HEAD:refs/heads/g/t").unwrap();
+
+        let clone_path = setup.work.parent().unwrap().join("clone");
+        let clone = init_work_repo(&clone_path, &setup.remote);
+        clone.fetch_branch("origin", "main").unwrap();
+        clone.fetch_branch("origin", "g/t").unwrap();
+        clone.checkout_branch("g/t").unwrap();
+        assert_eq!(
+            fs::read_to_string(clone_path.join("a.txt")).unwrap(),
+            "problem\n"
+        );
+        clone.checkout_branch("main").unwrap();
+        assert_eq!(
+            fs::read_to_string(clone_path.join("a.txt")).unwrap(),
+            "main\n"
+        );
+
+        let err = clone.checkout_branch("g/missing").unwrap_err();
+        assert!(matches!(err, GitError::BranchNotFound(_)));
+    }
+
+    #[test]
+    fn push_without_force_is_rejected_if_remote_moved() {
+        let (setup, repo) = setup();
+        let base = commit_and_push(&repo, &setup.work, "a.txt", "1\n");
+        commit_and_push(&repo, &setup.work, "a.txt", "2\n");
+
+        let tree = repo.repo.find_commit(base).unwrap().tree_id();
+        let diverged = repo.commit_tree(tree, base, "diverged").unwrap();
+        let err = repo
+            .push("origin", &format!("{diverged}:refs/heads/main"))
+            .unwrap_err();
+        assert!(matches!(err, GitError::PushRejected { .. }), "{err:?}");
+
+        repo.push("origin", &format!("+{diverged}:refs/heads/main"))
+            .unwrap();
+        assert_eq!(
+            remote_file(&setup.remote, "main", "a.txt").as_deref(),
+            Some("1\n")
+        );
+    }
+
+    #[test]
+    fn isolated_tree_contains_only_given_files() {
+        let (setup, repo) = setup();
+        let base = commit_and_push(&repo, &setup.work, "README.md", "readme\n");
+        write(&setup.work.join("problems/g/t/src/lib.rs"), "solution\n");
+        write(
+            &setup.work.join("problems/g/other/src/lib.rs"),
+            "unrelated\n",
+        );
+
+        let remote_heads = repo.list_remote("origin").unwrap();
+        assert_eq!(remote_heads.get("main"), Some(base));
+        assert_eq!(remote_heads.get("g/t"), None);
+        assert_eq!(repo.fetch_default_branch("origin").unwrap(), base);
+
+        let tree = repo
+            .tree_with_files(base, &[PathBuf::from("problems/g/t/src/lib.rs")], |_| false)
+            .unwrap();
+        let commit = repo.commit_tree(tree, base, "submit").unwrap();
+        let summary = repo.push_summary(Some(base), commit).unwrap();
+        assert!(summary.contains("problems/g/t/src/lib.rs"));
+        assert!(!summary.contains("other"));
+
+        repo.push("origin", &format!("{commit}:refs/heads/g/t"))
+            .unwrap();
+        assert_eq!(
+            remote_file(&setup.remote, "g/t", "problems/g/t/src/lib.rs").as_deref(),
+            Some("solution\n")
+        );
+        assert_eq!(
+            remote_file(&setup.remote, "g/t", "README.md").as_deref(),
+            Some("readme\n")
+        );
+        assert_eq!(
+            remote_file(&setup.remote, "g/t", "problems/g/other/src/lib.rs"),
+            None
+        );
+    }
+
+    #[test]
+    fn remote_tracking_state() {
+        let (setup, repo) = setup();
+        let pushed = commit_and_push(&repo, &setup.work, "a.txt", "1\n");
+
+        let clone_path = setup.work.parent().unwrap().join("clone");
+        let clone = init_work_repo(&clone_path, &setup.remote);
+        assert_eq!(
+            clone.remote_tracking_commit("origin", "main").unwrap(),
+            None
+        );
+        clone.fetch("origin").unwrap();
+        assert_eq!(
+            clone.remote_tracking_commit("origin", "main").unwrap(),
+            Some(pushed)
+        );
+
+        assert!(repo
+            .file_matches_commit(pushed, Path::new("a.txt"))
+            .unwrap());
+        write(&setup.work.join("a.txt"), "2\n");
+        assert!(!repo
+            .file_matches_commit(pushed, Path::new("a.txt"))
+            .unwrap());
+        assert!(!repo
+            .file_matches_commit(pushed, Path::new("b.txt"))
+            .unwrap());
+    }
+
+    #[test]
+    fn push_summary_lists_everything_force_pushed() {
+        let (setup, repo) = setup();
+        let base = commit_and_push(&repo, &setup.work, "README.md", "readme\n");
+        write(&setup.work.join("notes.txt"), "earlier\n");
+        repo.add(Path::new("notes.txt")).unwrap();
+        repo.commit("earlier work").unwrap();
+        write(&setup.work.join("problems/g/t/src/lib.rs"), "solution\n");
+        repo.add(Path::new("problems/g/t")).unwrap();
+        let head = repo.commit("solution").unwrap();
+
+        // The problem branch was submitted from another commit that is not in HEAD.
+        let tree = repo.repo.find_commit(base).unwrap().tree_id();
+        let other = repo.commit_tree(tree, base, "old submission").unwrap();
+        repo.push("origin", &format!("{other}:refs/heads/g/t"))
+            .unwrap();
+        let remote = repo.fetch_branch("origin", "g/t").unwrap();
+
+        let summary = repo.push_summary(Some(remote), head).unwrap();
+        assert!(summary.starts_with("2 new commit(s):\n"), "{summary}");
+        assert!(summary.contains("earlier work") && summary.contains(" solution\n"));
+        assert!(summary.contains("1 commit(s) on the remote will be overwritten"));
+        assert!(summary.contains("notes.txt") && summary.contains("problems/g/t/src/lib.rs"));
+        assert!(!summary.contains("README.md"));
+
+        let summary = repo.push_summary(None, head).unwrap();
+        assert!(summary.starts_with("3 new commit(s):\n"), "{summary}");
+        assert!(summary.contains("README.md"));
+    }
+
+    #[test]
+    fn missing_remote() {
+        let (_setup, repo) = setup();
+        let err = repo.push("upstream", "HEAD:refs/heads/main").unwrap_err();
+        assert!(matches!(err, GitError::RemoteNotFound(_)));
+    }
+}
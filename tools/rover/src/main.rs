@@ -1,21 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Arg, Command};
+use codestyle::style::check_problem_style;
 use colored::Colorize;
 use compose::run_compose::run_compose;
-use repository::repo::Repository;
+use overview::{list::list_problems, status::print_status};
+use repository::{context::LaunchOptions, repo::Repository, step::StepSelection};
 use std::io::Write;
 use std::{
     io,
     path::PathBuf,
     process::{ExitCode, Termination},
 };
-use submitting::submit::submit_problem;
-use testing::{report::ReportType, test::test_problem};
+use submitting::submit::{submit_problem, SubmitCheck, SubmitOptions};
+use testing::{
+    report::ReportType, test::test_problem, test_all::test_all_problems, watch::watch_problem,
+};
 
 #[cfg(feature = "check-version")]
 use util::check_version::ensure_version_is_latest;
 
+mod codestyle;
 mod compose;
+mod git;
+mod overview;
 mod repository;
 mod submitting;
 mod testing;
@@ -66,6 +73,28 @@ fn run_rover() -> Result<()> {
                         .default_value("Automatic message by rover-submit")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Run linters and show the changes before submitting (default is set by \"submit-check\" in \".config.yml\")")
+                        .required(false)
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("no-check")
+                        .long("no-check")
+                        .help("Submit without running the pre-submit checks")
+                        .required(false)
+                        .conflicts_with("check")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("isolated")
+                        .long("isolated")
+                        .help("Push a commit with only the problem's files on top of the remote problem branch, without force")
+                        .required(false)
+                        .takes_value(false)
+                )
         )
         .subcommand(
             Command::new("test")
@@ -98,9 +127,64 @@ fn run_rover() -> Result<()> {
                 .arg(
                     Arg::new("report-to")
                         .long("report-to")
-                        .help("Set system that will accept the results of testing")
+                        .help("Set system that will accept the results of testing (default is set by \"default-report\" in \"rover.toml\")")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Test every problem of the course the path belongs to and print a summary")
+                        .required(false)
+                        .conflicts_with_all(&["move-files", "report-to"])
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("step")
+                        .long("step")
+                        .help("Run only this step, can be repeated")
+                        .required(false)
+                        .multiple_occurrences(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("skip-step")
+                        .long("skip-step")
+                        .help("Don't run this step, can be repeated")
                         .required(false)
-                        .default_value("no-report")
+                        .multiple_occurrences(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("test")
+                        .long("test")
+                        .help("Run only the tests matching this filter in the cargo-test* commands")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("watch")
+                        .long("watch")
+                        .help("Re-run the steps every time the solution files are saved, cancelling the unfinished run")
+                        .required(false)
+                        .conflicts_with_all(&["all", "move-files", "report-to"])
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .help("Test only the problems of this group")
+                        .required(false)
+                        .requires("all")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Number of problems tested in parallel, output of failed problems is printed once they finish [default: 1]")
+                        .required(false)
+                        .requires("all")
                         .takes_value(true)
                 )
         )
@@ -122,6 +206,72 @@ fn run_rover() -> Result<()> {
                         .required(true)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("source-map")
+                        .long("source-map")
+                        .help("Path to the JSON file mapping public lines back to the private sources")
+                        .required(false)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List all problems of the course with their toolchains and steps")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path within the course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show which problems are submitted, modified since submission or never submitted")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path within the course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("fetch")
+                        .long("fetch")
+                        .help("Fetch the remote before comparing, otherwise the last fetched state is used")
+                        .required(false)
+                        .takes_value(false)
+                )
+        )
+        .subcommand(
+            Command::new("style")
+                .about("Check the solution files against the codestyle rules of the course projects")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to directory with a \".config.yml\" of the problem within course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("bench-baseline")
+                .about("Record the timings of the problem's benchmarks as the baseline of cargo-bench-gate")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to directory with a \".config.yml\" of the problem within course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
         )
         .arg_required_else_help(true)
         .get_matches();
@@ -134,7 +284,19 @@ fn run_rover() -> Result<()> {
             ensure_version_is_latest(&path)?;
 
             let message = submit_matches.value_of("message").unwrap();
-            submit_problem(&path, message)
+            let check = if submit_matches.is_present("check") {
+                SubmitCheck::Enabled
+            } else if submit_matches.is_present("no-check") {
+                SubmitCheck::Disabled
+            } else {
+                SubmitCheck::Default
+            };
+            let options = SubmitOptions {
+                message,
+                check,
+                isolated: submit_matches.is_present("isolated"),
+            };
+            submit_problem(&path, &options)
         }
         Some(("test", test_matches)) => {
             let path: PathBuf = test_matches.value_of("path").unwrap().into();
@@ -142,23 +304,81 @@ fn run_rover() -> Result<()> {
             #[cfg(feature = "check-version")]
             ensure_version_is_latest(&path)?;
 
+            let step_names = |name| {
+                test_matches
+                    .values_of(name)
+                    .map(|values| values.map(String::from).collect())
+                    .unwrap_or_default()
+            };
+            let selection = StepSelection::new(step_names("step"), step_names("skip-step"));
+            let options = LaunchOptions {
+                test_filter: test_matches.value_of("test").map(String::from),
+                ..Default::default()
+            };
+            if test_matches.is_present("all") {
+                let jobs = match test_matches.value_of("jobs") {
+                    Some(jobs) => jobs
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&jobs| jobs > 0)
+                        .context("--jobs must be a positive number")?,
+                    None => 1,
+                };
+                return test_all_problems(
+                    &path,
+                    test_matches.value_of("group"),
+                    jobs,
+                    &selection,
+                    &options,
+                );
+            }
+
             let repository = Repository::from_path(&path)?;
             let problem = repository.problem_from_path(&path)?;
-            let report = ReportType::from_name(test_matches.value_of("report-to").unwrap())?;
+            if test_matches.is_present("watch") {
+                return watch_problem(&problem, &selection, &options);
+            }
+            let report = ReportType::from_name(
+                test_matches
+                    .value_of("report-to")
+                    .unwrap_or_else(|| repository.course().default_report()),
+            )?;
             if let Some(solutions_repo) = test_matches.value_of("move-files") {
                 let checkout_branch = test_matches.value_of("checkout-branch").is_some();
                 let solutions_repo: PathBuf = solutions_repo.into();
                 problem.move_solution_files_from(&solutions_repo, checkout_branch)?;
             }
             // TODO: Make testing errors more clear
-            let testing_result = test_problem(problem);
-            let report_push = report.push_report(testing_result.is_err());
+            let testing_result = test_problem(&problem, &selection, &options);
+            let report_push = report.push_report(testing_result.is_err(), repository.course());
             testing_result.and(report_push)
         }
         Some(("compose", compose_matches)) => {
             let input: PathBuf = compose_matches.value_of("input").unwrap().into();
             let output: PathBuf = compose_matches.value_of("output").unwrap().into();
-            run_compose(&input, &output)
+            let source_map: Option<PathBuf> =
+                compose_matches.value_of("source-map").map(Into::into);
+            run_compose(&input, &output, source_map.as_deref())
+        }
+        Some(("list", list_matches)) => {
+            let path: PathBuf = list_matches.value_of("path").unwrap().into();
+            list_problems(&path)
+        }
+        Some(("status", status_matches)) => {
+            let path: PathBuf = status_matches.value_of("path").unwrap().into();
+            print_status(&path, status_matches.is_present("fetch"))
+        }
+        Some(("style", style_matches)) => {
+            let path: PathBuf = style_matches.value_of("path").unwrap().into();
+            let repository = Repository::from_path(&path)?;
+            check_problem_style(&repository.problem_from_path(&path)?)
+        }
+        Some(("bench-baseline", baseline_matches)) => {
+            let path: PathBuf = baseline_matches.value_of("path").unwrap().into();
+            let repository = Repository::from_path(&path)?;
+            repository
+                .problem_from_path(&path)?
+                .record_bench_baseline(&LaunchOptions::default())
         }
         _ => unreachable!(),
     }
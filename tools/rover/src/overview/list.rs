@@ -0,0 +1,39 @@
+use crate::repository::repo::Repository;
+use anyhow::Result;
+use colored::Colorize;
+use itertools::Itertools;
+use std::path::Path;
+
+pub fn list_problems(path: &Path) -> Result<()> {
+    let repo = Repository::from_path(path)?;
+    for problem in repo.problems()? {
+        let relative_path = problem.relative_path();
+        let config = match problem.config() {
+            Ok(config) => config,
+            Err(err) => {
+                println!(
+                    "{} {}: {err:#}",
+                    relative_path.display().to_string().bold(),
+                    "invalid config".red().bold(),
+                );
+                continue;
+            }
+        };
+        println!(
+            "{} (toolchain {})",
+            relative_path.display().to_string().bold(),
+            config.get_toolchain().name(),
+        );
+        for step in config.get_steps() {
+            println!(
+                "  {:>10}: {}",
+                step.name().green(),
+                step.commands()
+                    .iter()
+                    .map(|command| command.name())
+                    .join(", "),
+            );
+        }
+    }
+    Ok(())
+}
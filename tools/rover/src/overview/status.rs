@@ -0,0 +1,64 @@
+use crate::{
+    git::{GitRepository, DEFAULT_REMOTE as REMOTE},
+    repository::{problem::Problem, repo::Repository},
+};
+use anyhow::{Context, Result};
+use colored::{ColoredString, Colorize};
+use std::path::Path;
+
+enum SubmissionState {
+    NeverSubmitted,
+    Pushed,
+    ModifiedSincePush,
+}
+
+impl SubmissionState {
+    fn describe(&self) -> ColoredString {
+        match self {
+            Self::NeverSubmitted => "never submitted".dimmed(),
+            Self::Pushed => "pushed".green(),
+            Self::ModifiedSincePush => "modified since push".yellow(),
+        }
+    }
+}
+
+fn submission_state(git: &GitRepository, problem: &Problem) -> Result<SubmissionState> {
+    let commit = match git.remote_tracking_commit(REMOTE, &problem.branch_name())? {
+        Some(commit) => commit,
+        None => return Ok(SubmissionState::NeverSubmitted),
+    };
+    let config = problem.config()?;
+    for file in config.get_relative_user_files() {
+        let path = problem.relative_path().join(file);
+        if !git.file_matches_commit(commit, &path)? {
+            return Ok(SubmissionState::ModifiedSincePush);
+        }
+    }
+    Ok(SubmissionState::Pushed)
+}
+
+/// Compares solutions in the working directory with the remote problem branches.
+///
+/// Remote branches are taken from `refs/remotes/origin`, so they are as fresh as the last fetch.
+pub fn print_status(path: &Path, fetch: bool) -> Result<()> {
+    let repo = Repository::from_path(path)?;
+    let git = GitRepository::open(repo.get_path())?;
+    if fetch {
+        git.fetch(REMOTE)
+            .with_context(|| format!("failed to fetch {REMOTE}"))?;
+    }
+    let problems = repo.problems()?;
+    let width = problems
+        .iter()
+        .map(|problem| problem.branch_name().len())
+        .max()
+        .unwrap_or(0);
+    for problem in problems {
+        let state = match submission_state(&git, &problem) {
+            Ok(state) => state.describe(),
+            Err(err) => format!("error: {err:#}").red(),
+        };
+        println!("{:<width$}  {state}", problem.branch_name());
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! `cargo-bench-gate`: median timings of the problem's criterion benchmarks against a baseline.
+//!
+//! The baseline is recorded by `rover bench-baseline` on the reference solution in the private
+//! repository. It maps the full ids of the benchmarks to their medians in nanoseconds, so a
+//! solution with worse complexity fails regardless of how the tests exercise it.
+
+use super::{context::CommandContext, toolchain::Toolchain};
+use crate::console_println;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use itertools::Itertools;
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub(super) const DEFAULT_BASELINE: &str = ".bench-baseline.json";
+pub(super) const DEFAULT_TOLERANCE: f64 = 2.0;
+
+fn read_json(path: &Path) -> Result<serde_json::Value> {
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+    serde_json::from_str(&text).with_context(|| format!("failed to parse {path:?}"))
+}
+
+/// Medians of the benchmarks from the `new/` directories criterion writes for every benchmark.
+fn collect_medians(dir: &Path, medians: &mut BTreeMap<String, f64>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {dir:?}"))? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if !path.ends_with("new") {
+            collect_medians(&path, medians)?;
+            continue;
+        }
+        let benchmark = read_json(&path.join("benchmark.json"))?;
+        let estimates = read_json(&path.join("estimates.json"))?;
+        if let (Some(id), Some(median)) = (
+            benchmark["full_id"].as_str(),
+            estimates["median"]["point_estimate"].as_f64(),
+        ) {
+            medians.insert(id.to_string(), median);
+        }
+    }
+    Ok(())
+}
+
+fn run_benchmarks(
+    toolchain: &Toolchain,
+    context: &CommandContext,
+) -> Result<BTreeMap<String, f64>> {
+    let (target_dir, package) = toolchain.cargo_metadata(context)?;
+    // Results of the previous runs must not be mistaken for the current ones.
+    let criterion_home = target_dir.join("bench-gate").join(&package);
+    if criterion_home.exists() {
+        fs::remove_dir_all(&criterion_home)
+            .with_context(|| format!("failed to remove old results from {criterion_home:?}"))?;
+    }
+
+    let mut cmd = toolchain.build_command("cargo bench", context)?;
+    cmd.env("CRITERION_HOME", &criterion_home);
+    context.print_executing(&cmd);
+    if !context.run(&mut cmd)?.success() {
+        bail!("Benchmarks failed, see message above");
+    }
+
+    let mut medians = BTreeMap::new();
+    if criterion_home.exists() {
+        collect_medians(&criterion_home, &mut medians)?;
+    }
+    if medians.is_empty() {
+        bail!("no criterion benchmarks were run, check the `[[bench]]` targets of the problem");
+    }
+    Ok(medians)
+}
+
+fn format_time(nanoseconds: f64) -> String {
+    match nanoseconds {
+        time if time < 1e3 => format!("{time:.1} ns"),
+        time if time < 1e6 => format!("{:.1} µs", time / 1e3),
+        time if time < 1e9 => format!("{:.1} ms", time / 1e6),
+        time => format!("{:.2} s", time / 1e9),
+    }
+}
+
+/// Median of a benchmark of the solution next to the one of the reference solution.
+struct Comparison<'a> {
+    id: &'a str,
+    median: f64,
+    reference: f64,
+}
+
+impl Comparison<'_> {
+    fn ratio(&self) -> f64 {
+        self.median / self.reference
+    }
+
+    fn is_slow(&self, tolerance: f64) -> bool {
+        self.ratio() > tolerance
+    }
+}
+
+/// Pairs every benchmark of the baseline with its median, failing if one of them did not run.
+fn compare<'a>(
+    baseline: &'a BTreeMap<String, f64>,
+    medians: &BTreeMap<String, f64>,
+) -> Result<Vec<Comparison<'a>>> {
+    baseline
+        .iter()
+        .map(|(id, reference)| {
+            let median = medians
+                .get(id)
+                .with_context(|| format!("benchmark \"{id}\" from the baseline did not run"))?;
+            Ok(Comparison {
+                id,
+                median: *median,
+                reference: *reference,
+            })
+        })
+        .collect()
+}
+
+pub(super) fn run_bench_gate(
+    toolchain: &Toolchain,
+    baseline: &Path,
+    tolerance: f64,
+    context: &CommandContext,
+) -> Result<()> {
+    let path = context.get_workdir().join(baseline);
+    let text = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "failed to read the baseline {path:?}, \
+             record it with `rover bench-baseline` on the reference solution"
+        )
+    })?;
+    let baseline: BTreeMap<String, f64> =
+        serde_json::from_str(&text).with_context(|| format!("failed to parse {path:?}"))?;
+    let medians = run_benchmarks(toolchain, context)?;
+
+    let mut slow = vec![];
+    for comparison in compare(&baseline, &medians)? {
+        let verdict = if comparison.is_slow(tolerance) {
+            slow.push(comparison.id);
+            "Slow".red().bold()
+        } else {
+            "Fast".green().bold()
+        };
+        console_println!(
+            context.console(),
+            "{verdict:>12} {}: {} (reference {}, x{:.2})",
+            comparison.id,
+            format_time(comparison.median),
+            format_time(comparison.reference),
+            comparison.ratio()
+        );
+    }
+    if !slow.is_empty() {
+        bail!(
+            "{} of {} benchmarks are more than {tolerance} times slower than the reference solution: {}",
+            slow.len(),
+            baseline.len(),
+            slow.iter().join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Runs the benchmarks and saves their medians as the baseline.
+pub(super) fn record_baseline(
+    toolchain: &Toolchain,
+    baseline: &Path,
+    context: &CommandContext,
+) -> Result<()> {
+    let medians = run_benchmarks(toolchain, context)?;
+    let path = context.get_workdir().join(baseline);
+    let text = serde_json::to_string_pretty(&medians)? + "\n";
+    fs::write(&path, text).with_context(|| format!("failed to write {path:?}"))?;
+    for (id, median) in &medians {
+        console_println!(
+            context.console(),
+            "{:>12} {id}: {}",
+            "Recorded".green().bold(),
+            format_time(*median)
+        );
+    }
+    console_println!(
+        context.console(),
+        "{:>12} {} benchmarks to {}",
+        "Saved".green().bold(),
+        medians.len(),
+        baseline.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_estimates(dir: &Path, id: &str, median: f64) {
+        fs::create_dir_all(dir).unwrap();
+        let benchmark = json!({"group_id": "lru", "function_id": null, "full_id": id});
+        let estimates = json!({
+            "mean": {"point_estimate": median * 2.0},
+            "median": {"point_estimate": median},
+        });
+        fs::write(dir.join("benchmark.json"), benchmark.to_string()).unwrap();
+        fs::write(dir.join("estimates.json"), estimates.to_string()).unwrap();
+    }
+
+    #[test]
+    fn medians_of_criterion_results() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_estimates(&dir.path().join("lru/get/new"), "lru/get", 120.0);
+        write_estimates(&dir.path().join("lru/put/1000/new"), "lru/put/1000", 3.5e6);
+        // Results of the previous run and the report are not read.
+        write_estimates(&dir.path().join("lru/get/base"), "lru/get", 1.0);
+        fs::create_dir_all(dir.path().join("lru/report")).unwrap();
+
+        let mut medians = BTreeMap::new();
+        collect_medians(dir.path(), &mut medians).unwrap();
+        assert_eq!(
+            medians,
+            BTreeMap::from([
+                ("lru/get".to_string(), 120.0),
+                ("lru/put/1000".to_string(), 3.5e6),
+            ])
+        );
+    }
+
+    #[test]
+    fn time_units() {
+        assert_eq!(format_time(999.0), "999.0 ns");
+        assert_eq!(format_time(1500.0), "1.5 µs");
+        assert_eq!(format_time(2.25e6), "2.2 ms");
+        assert_eq!(format_time(3e9), "3.00 s");
+    }
+
+    #[test]
+    fn slow_beyond_tolerance() {
+        let baseline = BTreeMap::from([("fast".to_string(), 100.0), ("slow".to_string(), 100.0)]);
+        let medians = BTreeMap::from([
+            ("fast".to_string(), 200.0),
+            ("slow".to_string(), 201.0),
+            ("extra".to_string(), 1.0),
+        ]);
+        let slow: Vec<_> = compare(&baseline, &medians)
+            .unwrap()
+            .into_iter()
+            .filter(|comparison| comparison.is_slow(2.0))
+            .map(|comparison| comparison.id)
+            .collect();
+        assert_eq!(slow, ["slow"]);
+
+        let medians = BTreeMap::from([("fast".to_string(), 100.0)]);
+        assert!(compare(&baseline, &medians).is_err());
+    }
+}
@@ -1,8 +1,9 @@
-use std::process::{ExitStatus, Stdio};
-
+use super::bench_gate::{DEFAULT_BASELINE, DEFAULT_TOLERANCE};
+use crate::codestyle::rules::Rules;
 use anyhow::{anyhow, bail, Context, Result};
 use colored::Colorize;
-use itertools::Itertools;
+use serde_yaml::{Mapping, Value};
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum Command {
@@ -12,28 +13,34 @@ pub enum Command {
     CargoFmt,
     CargoClippy,
     CargoTest,
-    CargoTestValidate,
     CargoTestDebug,
-    CargoTestDebugValidate,
     CargoMiriTest,
+    /// Fails if less than `threshold` percent of the lines in the solution files are covered by tests.
+    CargoCoverage {
+        threshold: f64,
+    },
+    /// Fails if the medians of the problem's criterion benchmarks are more than `tolerance` times
+    /// the ones in the `baseline` file recorded on the reference solution.
+    CargoBenchGate {
+        baseline: PathBuf,
+        tolerance: f64,
+    },
+    /// Fails if less than `threshold` percent of the public items in the solution files are
+    /// documented, or less than `examples_threshold` percent have an `# Examples` section.
+    DocCoverage {
+        threshold: f64,
+        examples_threshold: f64,
+    },
+    /// Checks the solution files with the codestyle rules of the course projects.
+    Codestyle {
+        rules: Rules,
+    },
     CargoCompileTestMiniFrunk,
     CargoCompileTestOrm,
     CargoCompileTestSnapshot,
     PythonTest,
 }
 
-pub enum CommandStatus {
-    Ok,
-    ProcessFailed(ExitStatus),
-    ChecksFailed,
-}
-
-impl CommandStatus {
-    pub fn success(&self) -> bool {
-        matches!(self, CommandStatus::Ok)
-    }
-}
-
 impl Command {
     pub fn from_name(name: &str) -> Result<Self> {
         Ok(match name {
@@ -49,12 +56,88 @@ impl Command {
             "cargo-compile-test-orm" => Self::CargoCompileTestOrm,
             "cargo-compile-test-snapshot" => Self::CargoCompileTestSnapshot,
             "python-test" => Self::PythonTest,
+            "codestyle" => Self::Codestyle {
+                rules: Rules::default(),
+            },
+            "cargo-bench-gate" => Self::CargoBenchGate {
+                baseline: DEFAULT_BASELINE.into(),
+                tolerance: DEFAULT_TOLERANCE,
+            },
+            name @ ("cargo-coverage" | "doc-coverage") => {
+                bail!("command \"{name}\" requires a threshold, e.g. `{name}: {{threshold: 80}}`")
+            }
             name => bail!("command \"{name}\" is not supported"),
         })
     }
 
-    pub fn needs_nightly_toolchain(&self) -> bool {
-        matches!(self, Self::CargoTestValidate | Self::CargoTestDebugValidate)
+    /// Parses a command of a step: either its name or a mapping from the name to the parameters,
+    /// e.g. `cargo-coverage: {threshold: 80}`.
+    pub fn from_yml(value: &Value) -> Result<Self> {
+        if let Some(name) = value.as_str() {
+            return Self::from_name(name);
+        }
+        let mapping = value
+            .as_mapping()
+            .context("command is neither a name nor a mapping")?;
+        if mapping.len() != 1 {
+            bail!("command with parameters must be a mapping with a single key");
+        }
+        let (name, params) = mapping.iter().next().unwrap();
+        let name = name.as_str().context("command name is not a string")?;
+        let params = params
+            .as_mapping()
+            .with_context(|| format!("parameters of command \"{name}\" are not a mapping"))?;
+        match name {
+            "cargo-coverage" => {
+                check_params(name, params, &["threshold"])?;
+                Ok(Self::CargoCoverage {
+                    threshold: parse_threshold(name, params)?,
+                })
+            }
+            "cargo-bench-gate" => {
+                check_params(name, params, &["baseline", "tolerance"])?;
+                Ok(Self::CargoBenchGate {
+                    baseline: parse_baseline(name, params)?,
+                    tolerance: parse_tolerance(name, params)?,
+                })
+            }
+            "doc-coverage" => {
+                check_params(name, params, &["threshold", "examples-threshold"])?;
+                Ok(Self::DocCoverage {
+                    threshold: parse_threshold(name, params)?,
+                    examples_threshold: parse_percentage(name, params, "examples-threshold")?
+                        .unwrap_or(0.0),
+                })
+            }
+            "codestyle" => Ok(Self::Codestyle {
+                rules: Rules::from_yml(params)?,
+            }),
+            name => {
+                Self::from_name(name)?;
+                bail!("command \"{name}\" has no parameters")
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ForbidUnsafe => "forbid-unsafe",
+            Self::ForbidCollections => "forbid-collections",
+            Self::ForbidStd => "forbid-std",
+            Self::CargoFmt => "cargo-fmt",
+            Self::CargoClippy => "cargo-clippy",
+            Self::CargoTest => "cargo-test",
+            Self::CargoTestDebug => "cargo-test-debug",
+            Self::CargoMiriTest => "cargo-miri-test",
+            Self::CargoCoverage { .. } => "cargo-coverage",
+            Self::CargoBenchGate { .. } => "cargo-bench-gate",
+            Self::DocCoverage { .. } => "doc-coverage",
+            Self::Codestyle { .. } => "codestyle",
+            Self::CargoCompileTestMiniFrunk => "cargo-compile-test-mini-frunk",
+            Self::CargoCompileTestOrm => "cargo-compile-test-orm",
+            Self::CargoCompileTestSnapshot => "cargo-compile-test-snapshot",
+            Self::PythonTest => "python-test",
+        }
     }
 
     pub fn get_shell_line(&self) -> Result<String> {
@@ -65,12 +148,12 @@ impl Command {
             Self::CargoFmt => "cargo fmt --check".to_string(),
             Self::CargoClippy => "cargo clippy --release -- -D warnings".to_string(),
             Self::CargoTest => "cargo test --release".to_string(),
-            Self::CargoTestValidate => {
-                "cargo test --release -- -Z unstable-options --format json".to_string()
-            }
             Self::CargoTestDebug => "cargo test".to_string(),
-            Self::CargoTestDebugValidate => "cargo test -- -Z unstable-options --format json".to_string(),
             Self::CargoMiriTest => "cargo miri test --release".to_string(),
+            Self::CargoCoverage { .. } => bail!("no shell line for CargoCoverage"),
+            Self::CargoBenchGate { .. } => bail!("no shell line for CargoBenchGate"),
+            Self::DocCoverage { .. } => bail!("no shell line for DocCoverage"),
+            Self::Codestyle { .. } => bail!("no shell line for Codestyle"),
             Self::CargoCompileTestMiniFrunk => bail!("no shell line for CargoCompileTestMiniFrunk"),
             Self::CargoCompileTestOrm => bail!("no shell line for CargoCompileTestOrm"),
             Self::CargoCompileTestSnapshot => bail!("no shell line for CargoCompileTestSnapshot"),
@@ -78,56 +161,16 @@ impl Command {
         })
     }
 
-    pub fn cmd_stdout(&self) -> Stdio {
-        match self {
-            Self::CargoTestValidate | Self::CargoTestDebugValidate => Stdio::piped(),
-            _ => Stdio::inherit(),
-        }
-    }
-
-    pub fn wait(&self, process: &mut std::process::Child) -> Result<CommandStatus> {
-        match self {
-            Self::CargoTestValidate | Self::CargoTestDebugValidate => {
-                let stdout = process
-                    .stdout
-                    .as_mut()
-                    .context("Could not capture standard output")?;
-
-                let not_finished = cargotest::process_test_output(stdout)?;
-
-                let status = process.wait().context("Command was not running")?;
-                if status.success() {
-                    if !not_finished.is_empty() {
-                        let message = not_finished
-                            .into_iter()
-                            .sorted()
-                            .map(|name| format!("  {name}"))
-                            .join("\n");
-                        println!(
-                            "{}: some tests did not finish:\n{}\n",
-                            "error".red().bold(),
-                            message
-                        );
-                        return Ok(CommandStatus::ChecksFailed);
-                    }
-                    Ok(CommandStatus::Ok)
-                } else {
-                    Ok(CommandStatus::ProcessFailed(status))
-                }
-            }
-            _ => {
-                let status = process.wait().context("Command was not running")?;
-
-                if status.success() {
-                    Ok(CommandStatus::Ok)
-                } else {
-                    Ok(CommandStatus::ProcessFailed(status))
-                }
-            }
+    /// Arguments that make the launched libtest invocation run only the tests matching the filter.
+    /// `cargo-test` and `cargo-test-debug` pass the filter to the test binaries themselves.
+    pub fn test_filter_args(&self, filter: Option<&str>) -> Vec<String> {
+        match (self, filter) {
+            (Self::CargoMiriTest, Some(filter)) => vec!["--".to_string(), filter.to_string()],
+            _ => vec![],
         }
     }
 
-    pub fn get_failure_error(&self, _status: CommandStatus) -> anyhow::Error {
+    pub fn get_failure_error(&self) -> anyhow::Error {
         match self {
             Self::CargoFmt => anyhow!(
                 "Format your code as suggested above, \
@@ -142,115 +185,105 @@ impl Command {
     }
 }
 
-mod cargotest {
-    use std::{collections::HashSet, process::ChildStdout};
-
-    use anyhow::Result;
-    use colored::Colorize;
-
-    pub(super) fn print_log_line(log: &serde_json::Value) {
-        let format_result = |result| match result {
-            "ok" => "ok".green(),
-            error @ ("failed" | "timeout") => error.to_uppercase().red(),
-            result => result.normal(),
-        };
-        match (log["type"].as_str(), log["event"].as_str()) {
-            (Some("suite"), Some("started")) => {
-                println!(
-                    "\nrunning {test_count} tests",
-                    test_count = log["test_count"].as_i64().unwrap(),
-                );
-            }
-            (Some("suite"), Some(event @ ("ok" | "failed"))) => {
-                println!(
-                    "\ntest result: {result}. \
-                    {passed} passed; \
-                    {failed} failed; \
-                    {ignored} ignored; \
-                    {measured} measured; \
-                    {filtered_out} filtered out; \
-                    finished in {exec_time:.2}s\n",
-                    result = format_result(event),
-                    passed = log["passed"].as_i64().unwrap(),
-                    failed = log["failed"].as_i64().unwrap(),
-                    ignored = log["ignored"].as_i64().unwrap(),
-                    measured = log["measured"].as_i64().unwrap(),
-                    filtered_out = log["filtered_out"].as_i64().unwrap(),
-                    exec_time = log["exec_time"].as_f64().unwrap(),
-                );
-            }
-            (Some("test"), Some(event @ ("ok" | "failed" | "timeout"))) => {
-                println!(
-                    "test {name} ... {result}",
-                    name = log["name"].as_str().unwrap(),
-                    result = format_result(event),
-                );
-            }
-            (Some("test"), Some("started")) => {}
-            _ => println!("{log}"),
+fn check_params(command: &str, params: &Mapping, known: &[&str]) -> Result<()> {
+    for (key, _) in params.iter() {
+        match key.as_str() {
+            Some(key) if known.contains(&key) => {}
+            Some(key) => bail!("unknown parameter \"{key}\" of command \"{command}\""),
+            None => bail!("parameter name of command \"{command}\" is not a string"),
         }
     }
+    Ok(())
+}
 
-    pub(super) fn print_failures(failures: &Vec<serde_json::Value>) {
-        if failures.is_empty() {
-            return;
-        }
-
-        println!("\nfailures:\n");
-        for log in failures {
-            match (log["name"].as_str(), log["stdout"].as_str()) {
-                (Some(name), Some(stdout)) => {
-                    println!("---- {name} stdout ----");
-                    print!("{stdout}");
-                    if let Some(message) = log["message"].as_str() {
-                        println!("note: {message}");
-                    }
-                    println!();
-                }
-                _ => println!("{log}"),
-            }
-        }
-
-        println!("\nfailures:");
-        for test_name in failures.into_iter().filter_map(|log| log["name"].as_str()) {
-            println!("    {test_name}");
-        }
+fn parse_percentage(command: &str, params: &Mapping, key: &str) -> Result<Option<f64>> {
+    let Some(value) = params.get(&Value::String(key.to_string())) else {
+        return Ok(None);
+    };
+    let percentage = value
+        .as_f64()
+        .with_context(|| format!("{key} of command \"{command}\" is not a number"))?;
+    if !(0.0..=100.0).contains(&percentage) {
+        bail!("{key} of command \"{command}\" must be a percentage from 0 to 100");
     }
+    Ok(Some(percentage))
+}
 
-    pub(super) fn process_test_output(stdout: &mut ChildStdout) -> Result<HashSet<String>> {
-        let deserializer = serde_json::Deserializer::from_reader(stdout);
+fn parse_threshold(command: &str, params: &Mapping) -> Result<f64> {
+    parse_percentage(command, params, "threshold")?
+        .with_context(|| format!("command \"{command}\" requires a threshold"))
+}
 
-        let mut not_finished = HashSet::new();
-        let mut failures = vec![];
-        for log in deserializer.into_iter::<serde_json::Value>() {
-            let log = log?;
+fn parse_baseline(command: &str, params: &Mapping) -> Result<PathBuf> {
+    match params.get(&Value::String("baseline".to_string())) {
+        Some(value) => Ok(value
+            .as_str()
+            .with_context(|| format!("baseline of command \"{command}\" is not a path"))?
+            .into()),
+        None => Ok(DEFAULT_BASELINE.into()),
+    }
+}
 
-            if matches!(
-                (log["type"].as_str(), log["event"].as_str()),
-                (Some("test"), Some("failed"))
-            ) {
-                failures.push(log.clone());
-            }
+fn parse_tolerance(command: &str, params: &Mapping) -> Result<f64> {
+    let Some(value) = params.get(&Value::String("tolerance".to_string())) else {
+        return Ok(DEFAULT_TOLERANCE);
+    };
+    match value.as_f64() {
+        Some(tolerance) if tolerance >= 1.0 => Ok(tolerance),
+        _ => bail!("tolerance of command \"{command}\" must be a number not less than 1"),
+    }
+}
 
-            if matches!(
-                (log["type"].as_str(), log["event"].as_str()),
-                (Some("suite"), Some("ok" | "failed"))
-            ) {
-                print_failures(&failures);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
 
-            if log["type"] == "test" {
-                let name = log["name"].as_str().unwrap();
-                if log["event"] == "started" {
-                    not_finished.insert(name.to_owned());
-                } else if log["event"] == "ok" || log["event"] == "failed" {
-                    assert!(not_finished.remove(name));
-                }
-            }
+    fn parse(yml: &str) -> Result<Command> {
+        Command::from_yml(&serde_yaml::from_str(yml).unwrap())
+    }
 
-            print_log_line(&log);
+    #[test]
+    fn command_parameters() {
+        assert!(matches!(parse("cargo-test"), Ok(Command::CargoTest)));
+        assert!(matches!(
+            parse("cargo-coverage: {threshold: 80}"),
+            Ok(Command::CargoCoverage { threshold }) if threshold == 80.0
+        ));
+        assert!(matches!(
+            parse("cargo-coverage: {threshold: 72.5}"),
+            Ok(Command::CargoCoverage { threshold }) if threshold == 72.5
+        ));
+        assert!(matches!(
+            parse("cargo-bench-gate"),
+            Ok(Command::CargoBenchGate { baseline, tolerance })
+                if baseline == Path::new(DEFAULT_BASELINE) && tolerance == DEFAULT_TOLERANCE
+        ));
+        assert!(matches!(
+            parse("cargo-bench-gate: {baseline: benches/baseline.json, tolerance: 3}"),
+            Ok(Command::CargoBenchGate { baseline, tolerance })
+                if baseline == Path::new("benches/baseline.json") && tolerance == 3.0
+        ));
+        assert!(matches!(
+            parse("doc-coverage: {threshold: 100, examples-threshold: 50}"),
+            Ok(Command::DocCoverage { threshold, examples_threshold })
+                if threshold == 100.0 && examples_threshold == 50.0
+        ));
+        for yml in [
+            "cargo-coverage",
+            "cargo-coverage: {}",
+            "cargo-coverage: {threshold: 101}",
+            "cargo-coverage: {threshold: high}",
+            "cargo-coverage: {threshold: 80, branches: 50}",
+            "cargo-coverage: {threshold: 80, examples-threshold: 50}",
+            "doc-coverage",
+            "doc-coverage: {examples-threshold: 50}",
+            "cargo-bench-gate: {tolerance: 0.5}",
+            "cargo-bench-gate: {baseline: 1}",
+            "cargo-test: {threshold: 80}",
+            "{cargo-test: {}, cargo-fmt: {}}",
+        ] {
+            assert!(parse(yml).is_err(), "{yml}");
         }
-
-        Ok(not_finished)
     }
 }
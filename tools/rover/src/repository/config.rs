@@ -1,23 +1,38 @@
 #![allow(clippy::needless_question_mark)]
 
 use super::{
-    command::Command, context::CommandContext, step::Step, toolchain::Toolchain,
+    command::Command,
+    context::{CommandContext, LaunchOptions},
+    coverage::LLVM_TOOLS_COMPONENT,
+    requirements::{parse_version, Requirements},
+    step::Step,
+    toolchain::Toolchain,
 };
-use anyhow::{Context, Result};
-use glob::{glob_with, MatchOptions};
+use crate::codestyle::rules::Rules;
+use anyhow::{bail, Context, Result};
+use glob::{glob_with, MatchOptions, Pattern};
 use serde_yaml::{from_reader, Value};
 use std::{
     fs::File,
     path::{Path, PathBuf},
 };
 
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
 #[derive(Debug)]
 pub struct Config {
     workdir: PathBuf,
     toolchain: Toolchain,
     relative_user_files: Vec<PathBuf>,
     absolute_user_files: Vec<PathBuf>,
+    user_file_patterns: Vec<Pattern>,
     steps: Vec<Step>,
+    submit_check: bool,
+    requirements: Requirements,
 }
 
 impl Config {
@@ -42,6 +57,14 @@ impl Config {
             .collect::<Result<Vec<_>>>()?;
         let (relative_user_files, absolute_user_files) =
             Self::get_matching_user_files(&workdir, patterns.as_slice())?;
+        let user_file_patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = workdir.join(pattern);
+                Pattern::new(pattern.to_str().context("non-utf-8 path")?)
+                    .context("pattern is invalid")
+            })
+            .collect::<Result<Vec<_>>>()?;
         let steps = yml[&Value::String("steps".to_string())]
             .as_mapping()
             .context("steps is not a mapping")?
@@ -55,35 +78,65 @@ impl Config {
                     .as_sequence()
                     .context("the step commands are not a sequence")?
                     .iter()
-                    .flat_map(|value| {
-                        let mut cmds =
-                            vec![Command::from_name(value.as_str().unwrap())
-                                .unwrap()];
-
-                        if let Some(validate) = match cmds[0] {
-                            Command::CargoTest => {
-                                Some(Command::CargoTestValidate)
-                            }
-                            Command::CargoTestDebug => {
-                                Some(Command::CargoTestDebugValidate)
-                            }
-                            _ => None,
-                        } {
-                            cmds.push(validate);
-                        }
-
-                        cmds
-                    })
-                    .collect();
+                    .map(Command::from_yml)
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("invalid command in step \"{name}\""))?;
                 Ok(Step::new(name, commands))
             })
             .collect::<Result<Vec<_>>>()?;
+        let submit_check = match yml.get(&Value::String("submit-check".to_string())) {
+            Some(value) => value.as_bool().context("submit-check is not a bool")?,
+            None => false,
+        };
+        let mut components = match yml.get(&Value::String("components".to_string())) {
+            Some(value) => value
+                .as_sequence()
+                .context("components are not in a list")?
+                .iter()
+                .map(|value| {
+                    Ok(value
+                        .as_str()
+                        .context("component name is not a string")?
+                        .to_string())
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => vec![],
+        };
+        let needs_llvm_tools = steps
+            .iter()
+            .flat_map(Step::commands)
+            .any(|command| matches!(command, Command::CargoCoverage { .. }));
+        if needs_llvm_tools && toolchain == Toolchain::Empty {
+            bail!(
+                "cargo-coverage needs the {LLVM_TOOLS_COMPONENT} component, \
+                 which can't be installed for the \"empty\" toolchain"
+            );
+        }
+        if needs_llvm_tools && !components.iter().any(|name| name == LLVM_TOOLS_COMPONENT) {
+            components.push(LLVM_TOOLS_COMPONENT.to_string());
+        }
+        let min_rust_version = match yml.get(&Value::String("min-rust-version".to_string())) {
+            Some(value) => {
+                let version = value
+                    .as_str()
+                    .context("min-rust-version is not a string, quote it like \"1.70\"")?;
+                parse_version(version)?;
+                Some(version.to_string())
+            }
+            None => None,
+        };
         Ok(Self {
             workdir,
             toolchain,
             relative_user_files,
             absolute_user_files,
+            user_file_patterns,
             steps,
+            submit_check,
+            requirements: Requirements {
+                components,
+                min_rust_version,
+            },
         })
     }
 
@@ -91,32 +144,64 @@ impl Config {
         self.steps.as_slice()
     }
 
+    pub fn get_submit_check(&self) -> bool {
+        self.submit_check
+    }
+
     pub fn get_toolchain(&self) -> &Toolchain {
         &self.toolchain
     }
 
+    pub fn get_requirements(&self) -> &Requirements {
+        &self.requirements
+    }
+
     pub fn get_relative_user_files(&self) -> &[PathBuf] {
         self.relative_user_files.as_slice()
     }
 
-    #[allow(dead_code)]
     pub fn get_absolute_user_files(&self) -> &[PathBuf] {
         self.absolute_user_files.as_slice()
     }
 
-    pub fn get_command_context(&self) -> CommandContext {
-        CommandContext::new(&self.workdir, self.absolute_user_files.as_slice())
+    /// Rules of the first `codestyle` command of the steps.
+    pub fn get_codestyle_rules(&self) -> Option<&Rules> {
+        self.steps
+            .iter()
+            .flat_map(Step::commands)
+            .find_map(|command| match command {
+                Command::Codestyle { rules } => Some(rules),
+                _ => None,
+            })
+    }
+
+    /// Baseline file of the first `cargo-bench-gate` command of the steps.
+    pub fn get_bench_baseline(&self) -> Option<&Path> {
+        self.steps
+            .iter()
+            .flat_map(Step::commands)
+            .find_map(|command| match command {
+                Command::CargoBenchGate { baseline, .. } => Some(baseline.as_path()),
+                _ => None,
+            })
+    }
+
+    /// Whether the absolute path matches `allowed-patterns`, even if the file didn't exist
+    /// when the config was read.
+    pub fn matches_user_file(&self, path: &Path) -> bool {
+        self.user_file_patterns
+            .iter()
+            .any(|pattern| pattern.matches_path_with(path, MATCH_OPTIONS))
+    }
+
+    pub fn get_command_context(&self, options: &LaunchOptions) -> CommandContext {
+        CommandContext::new(&self.workdir, self.absolute_user_files.as_slice(), options)
     }
 
     fn get_matching_user_files(
         workdir: &Path,
         patterns: &[&str],
     ) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
-        let options = MatchOptions {
-            case_sensitive: false,
-            require_literal_separator: false,
-            require_literal_leading_dot: false,
-        };
         let workdir_len = workdir.iter().count();
         let mut relative_user_files = Vec::new();
         let mut absolute_user_files = Vec::new();
@@ -124,7 +209,7 @@ impl Config {
             let pattern = workdir.join(pattern).to_path_buf();
             let pattern = pattern.to_str().context("non-utf-8 path")?;
             for entry in
-                glob_with(pattern, options).context("pattern is invalid")?
+                glob_with(pattern, MATCH_OPTIONS).context("pattern is invalid")?
             {
                 let entry = entry?;
                 absolute_user_files.push(entry.clone());
@@ -135,3 +220,32 @@ impl Config {
         Ok((relative_user_files, absolute_user_files))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage_needs_a_toolchain() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".config.yml");
+        let config = |toolchain: &str| {
+            format!(
+                "toolchain: {toolchain}\n\
+                 allowed-patterns: []\n\
+                 steps:\n  coverage:\n    - cargo-coverage: {{threshold: 80}}\n"
+            )
+        };
+
+        std::fs::write(&path, config("stable")).unwrap();
+        let requirements = Config::from_yml(&path).unwrap().requirements;
+        assert_eq!(requirements.components, [LLVM_TOOLS_COMPONENT]);
+
+        std::fs::write(&path, config("empty")).unwrap();
+        let err = Config::from_yml(&path).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("\"empty\" toolchain"),
+            "{err:#}"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::{self, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+static LOG_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+struct Log {
+    path: PathBuf,
+    file: File,
+}
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Destination for the output of rover and of the commands it launches.
+///
+/// By default everything goes to the terminal. A captured console collects rover's messages
+/// together with stdout and stderr of the launched commands into a temporary log file, keeping
+/// their order, so that problems tested in parallel don't mix their output.
+#[derive(Clone, Default)]
+pub struct Console {
+    log: Option<Arc<Log>>,
+}
+
+impl Console {
+    pub fn captured() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "rover-{}-{}.log",
+            process::id(),
+            LOG_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .create_new(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to create log file {path:?}"))?;
+        Ok(Self {
+            log: Some(Arc::new(Log { path, file })),
+        })
+    }
+
+    pub fn print(&self, args: fmt::Arguments) {
+        match &self.log {
+            Some(log) => {
+                let _ = (&log.file).write_fmt(args);
+            }
+            None => {
+                let _ = io::stdout().write_fmt(args);
+            }
+        }
+    }
+
+    /// `Stdio` for stdout or stderr of a launched command.
+    pub fn stdio(&self) -> Result<Stdio> {
+        Ok(match &self.log {
+            Some(log) => Stdio::from(log.file.try_clone().context("failed to clone log file")?),
+            None => Stdio::inherit(),
+        })
+    }
+
+    /// Everything written to a captured console so far, empty for a terminal one.
+    pub fn captured_output(&self) -> String {
+        match &self.log {
+            Some(log) => fs::read_to_string(&log.path).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! console_println {
+    ($console: expr) => {
+        $console.print(format_args!("\n"))
+    };
+    ($console: expr, $($arg: tt)*) => {
+        $console.print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+#[macro_export]
+macro_rules! console_print {
+    ($console: expr, $($arg: tt)*) => {
+        $console.print(format_args!($($arg)*))
+    };
+}
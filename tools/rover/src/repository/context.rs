@@ -1,15 +1,90 @@
-use std::path::{Path, PathBuf};
+use super::{console::Console, process_group};
+use crate::console_println;
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use itertools::Itertools;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Child, ChildStdout, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Flag to stop a run from another thread: the running command is killed with everything
+/// it started, and the remaining commands are not started.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("the run was cancelled");
+        }
+        Ok(())
+    }
+
+    /// Waits for the child spawned by [`process_group::spawn`] to exit, killing its process
+    /// group if the run is cancelled meanwhile.
+    pub fn wait(&self, child: &mut Child) -> Result<ExitStatus> {
+        let result = self.poll(child);
+        process_group::release(child);
+        result
+    }
+
+    fn poll(&self, child: &mut Child) -> Result<ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait().context("Command was not running")? {
+                return Ok(status);
+            }
+            if self.is_cancelled() {
+                process_group::kill(child);
+                let _ = child.wait();
+                self.check()?;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Settings of a run shared by all of its commands.
+#[derive(Clone, Default)]
+pub struct LaunchOptions {
+    pub console: Console,
+    pub cancellation: Cancellation,
+    /// Filter passed to libtest by the `cargo-test*` commands.
+    pub test_filter: Option<String>,
+    /// The toolchains and components were installed beforehand, see
+    /// [`Problem::ensure_requirements`](super::problem::Problem::ensure_requirements).
+    pub requirements_ensured: bool,
+}
 
 pub struct CommandContext {
     workdir: PathBuf,
     user_files: Vec<PathBuf>,
+    options: LaunchOptions,
 }
 
 impl CommandContext {
-    pub fn new(workdir: &Path, user_files: &[PathBuf]) -> Self {
+    pub fn new(workdir: &Path, user_files: &[PathBuf], options: &LaunchOptions) -> Self {
         Self {
             workdir: workdir.to_path_buf(),
             user_files: user_files.to_vec(),
+            options: options.clone(),
         }
     }
 
@@ -20,4 +95,108 @@ impl CommandContext {
     pub fn get_user_files(&self) -> &[PathBuf] {
         &self.user_files
     }
+
+    pub fn console(&self) -> &Console {
+        &self.options.console
+    }
+
+    pub fn cancellation(&self) -> &Cancellation {
+        &self.options.cancellation
+    }
+
+    pub fn test_filter(&self) -> Option<&str> {
+        self.options.test_filter.as_deref()
+    }
+
+    /// The same context with all the tests selected.
+    pub fn without_test_filter(&self) -> Self {
+        Self {
+            workdir: self.workdir.clone(),
+            user_files: self.user_files.clone(),
+            options: LaunchOptions {
+                test_filter: None,
+                ..self.options.clone()
+            },
+        }
+    }
+
+    pub fn print_executing(&self, cmd: &process::Command) {
+        console_println!(
+            self.console(),
+            "{:>12} `{command}`{dir}",
+            "Executing".cyan().bold(),
+            command = std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy())
+                .join(" "),
+            dir = if let Some(dir) = cmd.get_current_dir() {
+                format!(" ({})", dir.to_string_lossy())
+            } else {
+                "".to_string()
+            },
+        );
+    }
+
+    /// Runs the command to completion with its stdout and stderr sent to the console.
+    pub fn run(&self, cmd: &mut process::Command) -> Result<ExitStatus> {
+        let _ = io::stdout().flush();
+        cmd.stdout(self.console().stdio()?)
+            .stderr(self.console().stdio()?);
+        let mut child = process_group::spawn(cmd).context("failed to execute command")?;
+        self.cancellation().wait(&mut child)
+    }
+
+    /// Runs the command to completion, passing its stdout to `read` on another thread.
+    /// Stderr is sent to the console. `read` must consume the whole output.
+    pub fn run_piped<T: Send>(
+        &self,
+        cmd: &mut process::Command,
+        read: impl FnOnce(ChildStdout) -> T + Send,
+    ) -> Result<(ExitStatus, T)> {
+        let _ = io::stdout().flush();
+        cmd.stdout(Stdio::piped()).stderr(self.console().stdio()?);
+        let mut child = process_group::spawn(cmd).context("failed to execute command")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("Could not capture standard output")?;
+        thread::scope(|scope| {
+            let reader = scope.spawn(move || read(stdout));
+            let status = self.cancellation().wait(&mut child);
+            let output = reader
+                .join()
+                .map_err(|_| anyhow!("output reader panicked"))?;
+            Ok((status?, output))
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{io::Read, time::Instant};
+
+    #[test]
+    fn cancel_kills_the_whole_process_group() {
+        let options = LaunchOptions::default();
+        let context = CommandContext::new(Path::new("."), &[], &options);
+        let cancellation = options.cancellation.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancellation.cancel();
+        });
+
+        let started = Instant::now();
+        // The background `sleep` keeps stdout open, so reading ends only once it is killed too.
+        let result = context.run_piped(
+            process::Command::new("sh").args(["-c", "sleep 30 & wait"]),
+            |mut stdout| {
+                let mut output = String::new();
+                let _ = stdout.read_to_string(&mut output);
+            },
+        );
+        canceller.join().unwrap();
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
 }
@@ -0,0 +1,200 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+pub const COURSE_CONFIG: &str = "rover.toml";
+
+const GROUP_PLACEHOLDER: &str = "{group}";
+const TITLE_PLACEHOLDER: &str = "{title}";
+
+/// Course-level settings read from `rover.toml` at the root of the course repository,
+/// which is the only place the course name and endpoints are defined.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Course {
+    name: String,
+    endpoints: Endpoints,
+    #[serde(default)]
+    branch_scheme: BranchScheme,
+    #[serde(default = "default_report")]
+    default_report: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Endpoints {
+    pub course: String,
+    pub scoreboard: String,
+    pub actions: String,
+    pub report: String,
+}
+
+/// Template of problem branch names with `{group}` and `{title}` placeholders.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(try_from = "String")]
+pub struct BranchScheme(String);
+
+fn default_report() -> String {
+    "no-report".to_string()
+}
+
+impl Default for BranchScheme {
+    fn default() -> Self {
+        Self(format!("{GROUP_PLACEHOLDER}/{TITLE_PLACEHOLDER}"))
+    }
+}
+
+impl TryFrom<String> for BranchScheme {
+    type Error = anyhow::Error;
+
+    fn try_from(scheme: String) -> Result<Self> {
+        for placeholder in [GROUP_PLACEHOLDER, TITLE_PLACEHOLDER] {
+            if scheme.matches(placeholder).count() != 1 {
+                bail!("branch scheme \"{scheme}\" must contain {placeholder} exactly once");
+            }
+        }
+        if scheme.contains("}{") {
+            bail!("placeholders in branch scheme \"{scheme}\" must be separated");
+        }
+        Ok(Self(scheme))
+    }
+}
+
+impl BranchScheme {
+    pub fn branch_name(&self, group: &str, title: &str) -> String {
+        self.0
+            .replace(GROUP_PLACEHOLDER, group)
+            .replace(TITLE_PLACEHOLDER, title)
+    }
+
+    /// Extracts `(group, title)` from a branch name made by this scheme.
+    pub fn parse(&self, branch: &str) -> Option<(String, String)> {
+        let group_pos = self.0.find(GROUP_PLACEHOLDER)?;
+        let title_pos = self.0.find(TITLE_PLACEHOLDER)?;
+        let (first_pos, first_len, second_pos, second_len) = if group_pos < title_pos {
+            (
+                group_pos,
+                GROUP_PLACEHOLDER.len(),
+                title_pos,
+                TITLE_PLACEHOLDER.len(),
+            )
+        } else {
+            (
+                title_pos,
+                TITLE_PLACEHOLDER.len(),
+                group_pos,
+                GROUP_PLACEHOLDER.len(),
+            )
+        };
+        let prefix = &self.0[..first_pos];
+        let middle = &self.0[first_pos + first_len..second_pos];
+        let suffix = &self.0[second_pos + second_len..];
+
+        let rest = branch.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        let (first, second) = rest.split_once(middle)?;
+        if first.is_empty() || second.is_empty() {
+            return None;
+        }
+        let (first, second) = (first.to_string(), second.to_string());
+        if group_pos < title_pos {
+            Some((first, second))
+        } else {
+            Some((second, first))
+        }
+    }
+}
+
+impl Course {
+    /// Reads `rover.toml` from the course root.
+    pub fn from_root(root: &Path) -> Result<Self> {
+        let path = root.join(COURSE_CONFIG);
+        if !path.exists() {
+            bail!("{COURSE_CONFIG} not found at the course root {root:?}");
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {path:?}"))?;
+        toml::from_str(&content).with_context(|| format!("failed to parse {path:?}"))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    pub fn branch_scheme(&self) -> &BranchScheme {
+        &self.branch_scheme
+    }
+
+    pub fn default_report(&self) -> &str {
+        &self.default_report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheme(scheme: &str) -> BranchScheme {
+        BranchScheme::try_from(scheme.to_string()).unwrap()
+    }
+
+    #[test]
+    fn default_scheme_round_trip() {
+        let scheme = BranchScheme::default();
+        assert_eq!(scheme.branch_name("iterators", "split"), "iterators/split");
+        assert_eq!(
+            scheme.parse("iterators/split"),
+            Some(("iterators".to_string(), "split".to_string()))
+        );
+        assert_eq!(scheme.parse("main"), None);
+    }
+
+    #[test]
+    fn custom_scheme() {
+        let scheme = scheme("submit/{title}-{group}");
+        assert_eq!(
+            scheme.branch_name("structs", "lru_cache"),
+            "submit/lru_cache-structs"
+        );
+        assert_eq!(
+            scheme.parse("submit/lru_cache-structs"),
+            Some(("structs".to_string(), "lru_cache".to_string()))
+        );
+        assert_eq!(scheme.parse("other/lru_cache-structs"), None);
+    }
+
+    #[test]
+    fn invalid_schemes() {
+        assert!(BranchScheme::try_from("{group}".to_string()).is_err());
+        assert!(BranchScheme::try_from("{group}{title}".to_string()).is_err());
+        assert!(BranchScheme::try_from("{group}/{title}/{title}".to_string()).is_err());
+    }
+
+    #[test]
+    fn partial_config_uses_defaults() {
+        let course: Course = toml::from_str(
+            r#"
+            name = "rust-hse-2025"
+            [endpoints]
+            course = "https://example.com"
+            scoreboard = "https://example.com/scoreboard"
+            actions = "https://example.com/actions"
+            report = "https://example.com/api/report"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(course.name(), "rust-hse-2025");
+        assert_eq!(course.default_report(), "no-report");
+        assert_eq!(course.branch_scheme().branch_name("g", "t"), "g/t");
+    }
+
+    #[test]
+    fn name_and_endpoints_are_required() {
+        assert!(toml::from_str::<Course>(r#"name = "rust-hse-2025""#).is_err());
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(Course::from_root(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! `cargo-coverage`: line coverage of the solution files by the problem's tests.
+//!
+//! The tests are built with `-C instrument-coverage` into a separate target directory and run
+//! like `cargo-test-debug` runs them. The raw profiles are merged with `llvm-profdata` and
+//! summarized with `llvm-cov` from the `llvm-tools` component. Doc tests are not run.
+
+use super::{
+    context::CommandContext,
+    libtest::{build_tests, build_tests_shell_line, TestBinary},
+    toolchain::Toolchain,
+};
+use crate::console_println;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::{
+    ffi::OsStr,
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process,
+};
+
+pub(super) const LLVM_TOOLS_COMPONENT: &str = "llvm-tools";
+
+fn llvm_tool(llvm_tools: &Path, name: &str) -> PathBuf {
+    llvm_tools.join(format!("{name}{}", std::env::consts::EXE_SUFFIX))
+}
+
+/// Directory with `llvm-profdata` and `llvm-cov` installed by the `llvm-tools` component.
+fn llvm_tools_dir(toolchain: &Toolchain, context: &CommandContext) -> Result<PathBuf> {
+    let sysroot = toolchain.output("rustc --print sysroot", context)?;
+    let version = toolchain.output("rustc -vV", context)?;
+    let host = version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .context("`rustc -vV` did not print the host")?;
+    let dir = Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(host)
+        .join("bin");
+    if !llvm_tool(&dir, "llvm-cov").exists() {
+        bail!(
+            "llvm-cov is not found in {dir:?}, install it with `rustup component add {LLVM_TOOLS_COMPONENT}`"
+        );
+    }
+    Ok(dir)
+}
+
+fn merge_profiles(llvm_tools: &Path, profiles: &Path, context: &CommandContext) -> Result<PathBuf> {
+    let mut raw_profiles = vec![];
+    for entry in fs::read_dir(profiles)? {
+        let path = entry?.path();
+        if path.extension() == Some(OsStr::new("profraw")) {
+            raw_profiles.push(path);
+        }
+    }
+    if raw_profiles.is_empty() {
+        bail!("the tests did not write any coverage profiles");
+    }
+    let merged = profiles.join("coverage.profdata");
+    let mut cmd = process::Command::new(llvm_tool(llvm_tools, "llvm-profdata"));
+    cmd.args(["merge", "-sparse"])
+        .args(&raw_profiles)
+        .arg("-o")
+        .arg(&merged);
+    context.print_executing(&cmd);
+    if !context.run(&mut cmd)?.success() {
+        bail!("Failed to merge coverage profiles, see message above");
+    }
+    Ok(merged)
+}
+
+/// Covered and total lines of every file in the `llvm-cov export` summary.
+fn export_summary(
+    llvm_tools: &Path,
+    profile: &Path,
+    binaries: &[TestBinary],
+    context: &CommandContext,
+) -> Result<Vec<(PathBuf, u64, u64)>> {
+    let mut cmd = process::Command::new(llvm_tool(llvm_tools, "llvm-cov"));
+    cmd.args(["export", "-summary-only"])
+        .arg(format!("-instr-profile={}", profile.display()));
+    for (i, binary) in binaries.iter().enumerate() {
+        if i > 0 {
+            cmd.arg("-object");
+        }
+        cmd.arg(binary.executable());
+    }
+    context.print_executing(&cmd);
+    let (status, export) = context.run_piped(&mut cmd, |stdout| {
+        serde_json::from_reader::<_, serde_json::Value>(BufReader::new(stdout))
+    })?;
+    if !status.success() {
+        bail!("Failed to export coverage, see message above");
+    }
+
+    Ok(summary_files(
+        &export.context("failed to parse llvm-cov export")?,
+    ))
+}
+
+fn summary_files(export: &serde_json::Value) -> Vec<(PathBuf, u64, u64)> {
+    let mut files = vec![];
+    for file in export["data"][0]["files"].as_array().into_iter().flatten() {
+        let lines = &file["summary"]["lines"];
+        if let (Some(name), Some(total), Some(covered)) = (
+            file["filename"].as_str(),
+            lines["count"].as_u64(),
+            lines["covered"].as_u64(),
+        ) {
+            files.push((name.into(), covered, total));
+        }
+    }
+    files
+}
+
+/// Files of the summary that are the solution files, other sources of the tests are skipped.
+fn solution_files(
+    files: Vec<(PathBuf, u64, u64)>,
+    user_files: &[PathBuf],
+) -> Vec<(PathBuf, u64, u64)> {
+    let user_files: Vec<_> = user_files
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    files
+        .into_iter()
+        .filter(|(path, _, _)| {
+            path.canonicalize()
+                .is_ok_and(|path| user_files.contains(&path))
+        })
+        .collect()
+}
+
+fn percent(covered: u64, total: u64) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / total as f64
+    }
+}
+
+pub(super) fn run_cargo_coverage(
+    toolchain: &Toolchain,
+    threshold: f64,
+    context: &CommandContext,
+) -> Result<()> {
+    // Lines run only by the tests left out by the filter would count as uncovered.
+    let context = &context.without_test_filter();
+    let (target_dir, package) = toolchain.cargo_metadata(context)?;
+    let coverage_dir = target_dir.join("coverage");
+    // Problems tested in parallel share the target directory, so each one has its own profiles.
+    let profiles = coverage_dir.join("profiles").join(&package);
+    if profiles.exists() {
+        fs::remove_dir_all(&profiles)
+            .with_context(|| format!("failed to remove old profiles from {profiles:?}"))?;
+    }
+    fs::create_dir_all(&profiles)
+        .with_context(|| format!("failed to create directory {profiles:?}"))?;
+    let llvm_tools = llvm_tools_dir(toolchain, context)?;
+
+    let mut cmd = toolchain.build_command(&build_tests_shell_line(false), context)?;
+    cmd.arg("--target-dir")
+        .arg(&coverage_dir)
+        .env("RUSTFLAGS", "-C instrument-coverage");
+    let build = build_tests(cmd, context)?;
+    let profile_file = profiles.join("%p-%m.profraw");
+    for binary in &build.binaries {
+        binary.run(context, &[("LLVM_PROFILE_FILE", profile_file.as_os_str())])?;
+    }
+    let merged = merge_profiles(&llvm_tools, &profiles, context)?;
+    let files = export_summary(&llvm_tools, &merged, &build.binaries, context)?;
+
+    let (mut covered, mut total) = (0, 0);
+    for (path, file_covered, file_total) in solution_files(files, context.get_user_files()) {
+        console_println!(
+            context.console(),
+            "{:>12} {}: {file_covered}/{file_total} lines ({:.1}%)",
+            "Coverage".cyan().bold(),
+            path.strip_prefix(context.get_workdir())
+                .unwrap_or(&path)
+                .display(),
+            percent(file_covered, file_total)
+        );
+        covered += file_covered;
+        total += file_total;
+    }
+    if total == 0 {
+        bail!("no lines of the solution files were found in the coverage report");
+    }
+
+    let coverage = percent(covered, total);
+    if coverage < threshold {
+        bail!(
+            "line coverage of the solution is {coverage:.1}% ({covered}/{total} lines), \
+             below the threshold of {threshold}%"
+        );
+    }
+    console_println!(
+        context.console(),
+        "{:>12} {coverage:.1}% of {total} lines, threshold is {threshold}%",
+        "Coverage".green().bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solution_lines_from_export() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("tests")).unwrap();
+        let lib = dir.path().join("src/lib.rs");
+        let tests = dir.path().join("tests/tests.rs");
+        fs::write(&lib, "").unwrap();
+        fs::write(&tests, "").unwrap();
+
+        // Trimmed output of `llvm-cov export -summary-only`.
+        let export = serde_json::json!({
+            "data": [{
+                "files": [
+                    {
+                        "filename": lib,
+                        "summary": {"lines": {"count": 40, "covered": 30, "percent": 75.0}}
+                    },
+                    {
+                        "filename": tests,
+                        "summary": {"lines": {"count": 10, "covered": 10, "percent": 100.0}}
+                    },
+                    {
+                        "filename": "/rustc/library/core/src/option.rs",
+                        "summary": {"lines": {"count": 5, "covered": 1, "percent": 20.0}}
+                    },
+                    {"filename": "no-summary.rs"}
+                ],
+                "totals": {"lines": {"count": 55, "covered": 41, "percent": 74.5}}
+            }],
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1"
+        });
+        let files = summary_files(&export);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0], (lib.clone(), 30, 40));
+
+        // The user files are given relative to another spelling of the same directory.
+        let user_files = [dir.path().join("src/../src/lib.rs")];
+        assert_eq!(solution_files(files, &user_files), [(lib, 30, 40)]);
+    }
+}
@@ -0,0 +1,250 @@
+//! `cargo test` on any toolchain with validation of the results.
+//!
+//! A test that calls `std::process::exit(0)` makes its binary exit successfully before the other
+//! tests have run. To catch this, the test binaries are built with `cargo test --no-run`, and
+//! every non-ignored test from `--list` must report a result in the output of its binary.
+
+use super::{context::CommandContext, toolchain::filtered_env, toolchain::Toolchain};
+use crate::console_println;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use itertools::Itertools;
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Test executable built by `cargo test --no-run`.
+pub(super) struct TestBinary {
+    executable: PathBuf,
+    manifest_dir: PathBuf,
+    is_lib: bool,
+    name: String,
+}
+
+pub(super) struct TestBuild {
+    pub binaries: Vec<TestBinary>,
+    pub has_doc_tests: bool,
+}
+
+fn profile_args(release: bool) -> &'static str {
+    if release {
+        " --release"
+    } else {
+        ""
+    }
+}
+
+pub(super) fn build_tests_shell_line(release: bool) -> String {
+    format!(
+        "cargo test{} --no-run --message-format=json-render-diagnostics",
+        profile_args(release)
+    )
+}
+
+/// Builds the test binaries with `cmd`, a `cargo test --no-run` from `build_tests_shell_line`.
+pub(super) fn build_tests(
+    mut cmd: process::Command,
+    context: &CommandContext,
+) -> Result<TestBuild> {
+    context.print_executing(&cmd);
+    let (status, messages) = context.run_piped(&mut cmd, |stdout| {
+        BufReader::new(stdout)
+            .lines()
+            .map(|line| Ok(serde_json::from_str::<serde_json::Value>(&line?)?))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    if !status.success() {
+        bail!("Failed to build tests, see message above");
+    }
+
+    let mut build = TestBuild {
+        binaries: vec![],
+        has_doc_tests: false,
+    };
+    for message in messages.context("failed to parse cargo messages")? {
+        if message["reason"] != "compiler-artifact" {
+            continue;
+        }
+        let manifest_dir = message["manifest_path"]
+            .as_str()
+            .and_then(|path| Path::new(path).parent())
+            .context("compiler artifact has no manifest path")?;
+        let target = &message["target"];
+        if manifest_dir == context.get_workdir() && target["doctest"] == true {
+            build.has_doc_tests = true;
+        }
+        if let (true, Some(executable)) = (
+            message["profile"]["test"] == true,
+            message["executable"].as_str(),
+        ) {
+            build.binaries.push(TestBinary {
+                executable: executable.into(),
+                manifest_dir: manifest_dir.to_path_buf(),
+                is_lib: target["kind"]
+                    .as_array()
+                    .is_some_and(|kinds| kinds.iter().any(|kind| kind == "lib")),
+                name: target["name"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+    }
+    // The same order as `cargo test` runs them in: unit tests of the library first.
+    build
+        .binaries
+        .sort_by(|a, b| (!a.is_lib, &a.name).cmp(&(!b.is_lib, &b.name)));
+    Ok(build)
+}
+
+impl TestBinary {
+    pub fn executable(&self) -> &Path {
+        &self.executable
+    }
+
+    fn command(&self, context: &CommandContext, envs: &[(&str, &OsStr)]) -> process::Command {
+        let mut cmd = process::Command::new(&self.executable);
+        cmd.current_dir(&self.manifest_dir)
+            .env_clear()
+            .envs(filtered_env())
+            .env("CARGO_MANIFEST_DIR", &self.manifest_dir)
+            .envs(envs.iter().copied())
+            .args(context.test_filter());
+        cmd
+    }
+
+    /// Names of the tests from `--list`, `None` if the binary doesn't support it.
+    fn list(
+        &self,
+        context: &CommandContext,
+        envs: &[(&str, &OsStr)],
+        ignored: bool,
+    ) -> Result<Option<HashSet<String>>> {
+        let mut cmd = self.command(context, envs);
+        cmd.args(["--list", "--format", "terse"]);
+        if ignored {
+            cmd.arg("--ignored");
+        }
+        let output = cmd.output().context("failed to list tests")?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.strip_suffix(": test"))
+                .map(String::from)
+                .collect(),
+        ))
+    }
+
+    /// Runs the tests with additional environment variables and checks that all of them finished.
+    pub fn run(&self, context: &CommandContext, envs: &[(&str, &OsStr)]) -> Result<()> {
+        let expected = match (
+            self.list(context, envs, false)?,
+            self.list(context, envs, true)?,
+        ) {
+            (Some(all), Some(ignored)) => Some(&all - &ignored),
+            _ => None,
+        };
+
+        let mut cmd = self.command(context, envs);
+        context.print_executing(&cmd);
+        let console = context.console();
+        let (status, finished) = context.run_piped(&mut cmd, |stdout| {
+            let mut finished = HashSet::new();
+            for line in BufReader::new(stdout).lines() {
+                let line = line?;
+                console_println!(console, "{line}");
+                if let Some(name) = finished_test_name(&line) {
+                    finished.insert(name.to_string());
+                }
+            }
+            anyhow::Ok(finished)
+        })?;
+        if !status.success() {
+            bail!("Tests of `{}` failed, see message above", self.name.bold());
+        }
+
+        let finished = finished?;
+        if let Some(expected) = expected {
+            let not_finished = expected
+                .difference(&finished)
+                .sorted()
+                .map(|name| format!("  {name}"))
+                .join("\n");
+            if !not_finished.is_empty() {
+                bail!(
+                    "some tests of `{}` did not finish:\n{not_finished}",
+                    self.name.bold()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name of the test from a result line like `test tests::it_works ... ok`.
+fn finished_test_name(line: &str) -> Option<&str> {
+    let (name, result) = line.strip_prefix("test ")?.rsplit_once(" ...")?;
+    if result.trim().is_empty() {
+        // The name is printed before the result if the test runs for long, or if it never finishes.
+        return None;
+    }
+    Some(name.strip_suffix(" - should panic").unwrap_or(name))
+}
+
+fn run_doc_tests(toolchain: &Toolchain, release: bool, context: &CommandContext) -> Result<()> {
+    let mut cmd = toolchain.build_command(
+        &format!("cargo test{} --doc", profile_args(release)),
+        context,
+    )?;
+    if let Some(filter) = context.test_filter() {
+        cmd.args(["--", filter]);
+    }
+    context.print_executing(&cmd);
+    if !context.run(&mut cmd)?.success() {
+        bail!("Doc tests failed, see message above");
+    }
+    Ok(())
+}
+
+pub(super) fn run_cargo_test(
+    toolchain: &Toolchain,
+    release: bool,
+    context: &CommandContext,
+) -> Result<()> {
+    let build = build_tests(
+        toolchain.build_command(&build_tests_shell_line(release), context)?,
+        context,
+    )?;
+    for binary in &build.binaries {
+        binary.run(context, &[])?;
+    }
+    if build.has_doc_tests {
+        run_doc_tests(toolchain, release, context)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_lines() {
+        assert_eq!(finished_test_name("test it_works ... ok"), Some("it_works"));
+        assert_eq!(
+            finished_test_name("test tests::overflow - should panic ... FAILED"),
+            Some("tests::overflow")
+        );
+        assert_eq!(finished_test_name("test slow ... ignored"), Some("slow"));
+        assert_eq!(finished_test_name("test exits_early ... "), None);
+        assert_eq!(finished_test_name("running 3 tests"), None);
+        assert_eq!(
+            finished_test_name("test result: ok. 3 passed; 0 failed; 0 ignored"),
+            None
+        );
+    }
+}
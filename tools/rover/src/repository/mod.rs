@@ -1,8 +1,15 @@
+mod bench_gate;
 mod command;
 mod config;
-mod context;
+pub mod console;
+pub mod context;
 mod copying;
+pub mod course;
+mod coverage;
+mod libtest;
 pub mod problem;
+mod process_group;
 pub mod repo;
-mod step;
+mod requirements;
+pub mod step;
 mod toolchain;
@@ -1,26 +1,44 @@
+use super::bench_gate::record_baseline;
 use super::config::Config;
+use super::context::{CommandContext, LaunchOptions};
+use super::course::BranchScheme;
 use super::repo::PROBLEMS_FOLDER;
-use crate::{launch_git, repository::{copying::copy_files, toolchain::Toolchain}};
-use anyhow::{bail, Result};
-use colored::Colorize;
-use std::{
-    path::{Path, PathBuf},
-    process,
+use super::step::Step;
+use crate::{
+    console_println,
+    git::GitRepository,
+    repository::{copying::copy_files, toolchain::Toolchain},
 };
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
 
-const DEFAULT_YML_NAME: &str = ".config.yml";
+pub const DEFAULT_YML_NAME: &str = ".config.yml";
+pub const LINT_STEP_NAME: &str = "linters";
+
+/// Result of a single step, `None` if it was skipped because an earlier step failed.
+pub struct StepReport {
+    pub name: String,
+    pub result: Option<Result<()>>,
+}
 
 pub struct Problem {
     path: PathBuf,
+    branch_scheme: BranchScheme,
 }
 
 impl Problem {
-    pub(super) fn from_path(path: &Path) -> Self {
+    pub(super) fn from_path(path: &Path, branch_scheme: &BranchScheme) -> Self {
         Self {
             path: path.to_path_buf(),
+            branch_scheme: branch_scheme.clone(),
         }
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn title(&self) -> String {
         self.path.file_name().unwrap().to_str().unwrap().to_string()
     }
@@ -37,7 +55,7 @@ impl Problem {
     }
 
     pub fn branch_name(&self) -> String {
-        self.group() + "/" + &self.title()
+        self.branch_scheme.branch_name(&self.group(), &self.title())
     }
 
     pub fn relative_path(&self) -> PathBuf {
@@ -50,24 +68,103 @@ impl Problem {
         Config::from_yml(&self.path.join(DEFAULT_YML_NAME))
     }
 
-    pub fn launch_all_steps(&self) -> Result<()> {
+    pub fn launch_steps(
+        &self,
+        filter: impl Fn(&Step) -> bool,
+        options: &LaunchOptions,
+    ) -> Result<()> {
+        for report in self.run_steps(filter, options)? {
+            if let Some(Err(err)) = report.result {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Installs the toolchains and components the problems need, once for each distinct set
+    /// of requirements. Problems whose config cannot be read are skipped.
+    pub fn ensure_requirements(problems: &[Problem], options: &LaunchOptions) -> Result<()> {
+        let configs: Vec<_> = problems
+            .iter()
+            .filter_map(|problem| problem.config().ok())
+            .collect();
+        let mut ensured = vec![];
+        for config in &configs {
+            let required = (config.get_toolchain(), config.get_requirements());
+            if !ensured.contains(&required) {
+                required.1.ensure(required.0, &options.console)?;
+                ensured.push(required);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the selected steps in order, stopping at the first failed one.
+    /// Fails only if the problem's config cannot be read.
+    pub fn run_steps(
+        &self,
+        filter: impl Fn(&Step) -> bool,
+        options: &LaunchOptions,
+    ) -> Result<Vec<StepReport>> {
         let config = self.config()?;
         let toolchain = config.get_toolchain();
-        let context = config.get_command_context();
-        for step in config.get_steps() {
-            println!("{:>12} {}", "Step".green().bold(), step.name());
-            for command in step.commands() {
-                let toolchain = if command.needs_nightly_toolchain() {
-                    &Toolchain::Nightly
-                } else {
-                    toolchain
-                };
-                println!(
-                    "{:>12} {command:?} (toolchain {toolchain:?})",
-                    "Running cmd".green().bold()
+        let context = config.get_command_context(options);
+        if !options.requirements_ensured {
+            config
+                .get_requirements()
+                .ensure(toolchain, &options.console)?;
+        }
+        let mut reports = vec![];
+        let mut failed = false;
+        for step in config.get_steps().iter().filter(|step| filter(step)) {
+            let result = if failed {
+                None
+            } else {
+                console_println!(
+                    options.console,
+                    "{:>12} {}",
+                    "Step".green().bold(),
+                    step.name()
                 );
-                toolchain.run_command(command, &context)?;
-            }
+                let result = Self::run_step_commands(step, toolchain, &context);
+                failed = result.is_err();
+                Some(result)
+            };
+            reports.push(StepReport {
+                name: step.name().to_string(),
+                result,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Runs the benchmarks of the `cargo-bench-gate` command and saves their timings as its
+    /// baseline. Meant to be run on the reference solution.
+    pub fn record_bench_baseline(&self, options: &LaunchOptions) -> Result<()> {
+        let config = self.config()?;
+        let baseline = config
+            .get_bench_baseline()
+            .context("the problem has no cargo-bench-gate command")?;
+        let toolchain = config.get_toolchain();
+        config
+            .get_requirements()
+            .ensure(toolchain, &options.console)?;
+        record_baseline(toolchain, baseline, &config.get_command_context(options))
+    }
+
+    fn run_step_commands(
+        step: &Step,
+        toolchain: &Toolchain,
+        context: &CommandContext,
+    ) -> Result<()> {
+        for command in step.commands() {
+            context.cancellation().check()?;
+            console_println!(
+                context.console(),
+                "{:>12} {command:?} (toolchain {toolchain:?})",
+                "Running cmd".green().bold()
+            );
+            toolchain.run_command(command, context)?;
         }
         Ok(())
     }
@@ -82,8 +179,10 @@ impl Problem {
         let branch_name = self.branch_name();
         let solutions_problem_path = solutions_repo.join(relative_path);
         let repository_problem_path = self.path.clone();
-        if checkout_branch && !launch_git!(solutions_repo, "checkout", &branch_name) {
-            bail!("failed to checkout branch in solutions repository")
+        if checkout_branch {
+            GitRepository::open(solutions_repo)?
+                .checkout_branch(&branch_name)
+                .context("failed to checkout branch in solutions repository")?;
         }
         copy_files(
             &solutions_problem_path,
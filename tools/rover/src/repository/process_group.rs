@@ -0,0 +1,102 @@
+//! Commands run in their own process group, so that the test binaries and build scripts
+//! started by `cargo` can be killed along with it.
+//!
+//! Such commands no longer get the `SIGINT` of Ctrl-C from the terminal, so rover forwards
+//! `SIGINT` and `SIGTERM` to the groups that are still running before exiting.
+
+use std::{
+    io,
+    process::{Child, Command},
+};
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        os::unix::process::CommandExt,
+        process::Command,
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            Once,
+        },
+    };
+
+    /// More than the number of commands rover runs at once, `0` marks a free slot.
+    const SLOTS: usize = 256;
+    #[allow(clippy::declare_interior_mutable_const)]
+    const FREE: AtomicI32 = AtomicI32::new(0);
+    static GROUPS: [AtomicI32; SLOTS] = [FREE; SLOTS];
+    static INSTALL: Once = Once::new();
+
+    extern "C" fn forward(signal: libc::c_int) {
+        // Only async-signal-safe calls here.
+        for group in &GROUPS {
+            let group = group.load(Ordering::Relaxed);
+            if group > 0 {
+                unsafe { libc::kill(-group, signal) };
+            }
+        }
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+
+    pub fn prepare(cmd: &mut Command) {
+        INSTALL.call_once(|| {
+            let handler = forward as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            unsafe {
+                libc::signal(libc::SIGINT, handler);
+                libc::signal(libc::SIGTERM, handler);
+            }
+        });
+        cmd.process_group(0);
+    }
+
+    pub fn track(pid: u32) {
+        let pid = pid as i32;
+        // If every slot is taken the group is not forwarded signals, which only matters on Ctrl-C.
+        GROUPS.iter().any(|slot| {
+            slot.compare_exchange(0, pid, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        });
+    }
+
+    pub fn untrack(pid: u32) {
+        let pid = pid as i32;
+        if let Some(slot) = GROUPS
+            .iter()
+            .find(|slot| slot.load(Ordering::Relaxed) == pid)
+        {
+            slot.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn kill(pid: u32) {
+        unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+    }
+}
+
+/// Spawns the command as the leader of a new process group.
+pub fn spawn(cmd: &mut Command) -> io::Result<Child> {
+    #[cfg(unix)]
+    unix::prepare(cmd);
+    let child = cmd.spawn()?;
+    #[cfg(unix)]
+    unix::track(child.id());
+    Ok(child)
+}
+
+/// Kills the child and everything in its process group.
+pub fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    unix::kill(child.id());
+    let _ = child.kill();
+}
+
+/// Forgets the group of a child that has exited.
+pub fn release(child: &Child) {
+    #[cfg(unix)]
+    unix::untrack(child.id());
+    #[cfg(not(unix))]
+    let _ = child;
+}
@@ -1,13 +1,33 @@
-use super::problem::Problem;
+use super::{
+    course::Course,
+    problem::{Problem, DEFAULT_YML_NAME},
+};
 use crate::compose;
 use anyhow::{bail, Context, Result};
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 pub const COMPOSE_CONFIG: &str = "compose.yml";
 pub const PROBLEMS_FOLDER: &str = "problems";
 
+fn read_dirs(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    for entry in fs::read_dir(path).with_context(|| format!("failed to read directory {path:?}"))? {
+        let path = entry
+            .with_context(|| format!("failed to read entry in directory {path:?}"))?
+            .path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
 pub struct Repository {
     path: PathBuf,
+    course: Course,
 }
 
 impl Repository {
@@ -20,8 +40,10 @@ impl Repository {
                 bail!("path {path:?} does not contain course repository")
             }
         }
+        let course = Course::from_root(&canon_path)?;
         Ok(Self {
             path: canon_path,
+            course,
         })
     }
 
@@ -44,16 +66,38 @@ impl Repository {
             .unwrap()
             .to_string();
         if self.path.join(PROBLEMS_FOLDER).join(group).join(title) == path {
-            Ok(Problem::from_path(&path))
+            Ok(Problem::from_path(&path, self.course.branch_scheme()))
         } else {
             bail!("problem path is not in REPOSITORY/problems/GROUP/TITLE")
         }
     }
 
+    /// All problems of the course, i.e. `problems/GROUP/TITLE` directories with a config, sorted by path.
+    pub fn problems(&self) -> Result<Vec<Problem>> {
+        let problems_path = self.path.join(PROBLEMS_FOLDER);
+        let mut paths = vec![];
+        for group in read_dirs(&problems_path)? {
+            for problem in read_dirs(&group)? {
+                if problem.join(DEFAULT_YML_NAME).is_file() {
+                    paths.push(problem);
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths
+            .iter()
+            .map(|path| Problem::from_path(path, self.course.branch_scheme()))
+            .collect())
+    }
+
     pub fn compose_config(&self) -> Result<compose::config::Config> {
         compose::config::Config::from_yml(&self.path.join(COMPOSE_CONFIG))
     }
 
+    pub fn course(&self) -> &Course {
+        &self.course
+    }
+
     pub fn get_path(&self) -> &Path {
         &self.path
     }
@@ -0,0 +1,208 @@
+use super::{console::Console, toolchain::Toolchain};
+use crate::console_println;
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use std::process;
+use version_compare::Version;
+
+/// What the problem needs from its toolchain: `components` and `min-rust-version` in `.config.yml`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Requirements {
+    pub components: Vec<String>,
+    pub min_rust_version: Option<String>,
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to execute `{program}`"))?;
+    if !output.status.success() {
+        bail!(
+            "`{program} {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn parse_version(version: &str) -> Result<Version<'_>> {
+    Version::from(version).ok_or_else(|| anyhow!("could not parse version \"{version}\""))
+}
+
+/// Version of rustc from the toolchain, e.g. `1.83.0` for `rustc 1.83.0-nightly (90b35a623 2024-11-26)`.
+fn rustc_version(toolchain: &Toolchain) -> Result<String> {
+    let output = match toolchain.rustup_name() {
+        Some(name) => run("rustup", &["run", name, "rustc", "--version"])?,
+        None => run("rustc", &["--version"])?,
+    };
+    output
+        .split_whitespace()
+        .nth(1)
+        .and_then(|version| version.split('-').next())
+        .map(String::from)
+        .with_context(|| format!("unexpected output of `rustc --version`: {output}"))
+}
+
+fn check_min_version(toolchain: &Toolchain, version: &str, min_version: &str) -> Result<()> {
+    if parse_version(version)? >= parse_version(min_version)? {
+        return Ok(());
+    }
+    let hint = match toolchain {
+        Toolchain::Stable | Toolchain::Nightly => {
+            format!("update it with `rustup update {}`", toolchain.name())
+        }
+        Toolchain::Empty => "update the Rust installation in PATH".to_string(),
+        Toolchain::Pinned(_) => "the problem config pins an older toolchain".to_string(),
+    };
+    bail!(
+        "the problem requires Rust {min_version} or newer, but toolchain \"{}\" has {version}, {hint}",
+        toolchain.name()
+    )
+}
+
+fn ensure_installed(name: &str, console: &Console) -> Result<()> {
+    if run("rustup", &["run", name, "rustc", "--version"]).is_ok() {
+        return Ok(());
+    }
+    console_println!(
+        console,
+        "{:>12} toolchain {name}",
+        "Installing".green().bold()
+    );
+    run(
+        "rustup",
+        &["toolchain", "install", name, "--profile", "minimal"],
+    )
+    .with_context(|| {
+        format!(
+            "failed to install toolchain {name}, check the network connection \
+             or install it with `rustup toolchain install {name}`"
+        )
+    })?;
+    Ok(())
+}
+
+fn ensure_components(name: &str, components: &[String], console: &Console) -> Result<()> {
+    let installed = run(
+        "rustup",
+        &["component", "list", "--installed", "--toolchain", name],
+    )?;
+    // Components built for a target are listed with it, e.g. `clippy-x86_64-unknown-linux-gnu`.
+    let host = installed
+        .lines()
+        .find_map(|line| line.strip_prefix("rustc-"))
+        .with_context(|| format!("toolchain {name} has no rustc component"))?;
+    let missing: Vec<_> = components
+        .iter()
+        .map(String::as_str)
+        .filter(|component| {
+            !installed
+                .lines()
+                .any(|line| line == *component || line == format!("{component}-{host}"))
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    console_println!(
+        console,
+        "{:>12} {} for toolchain {name}",
+        "Installing".green().bold(),
+        missing.join(", ")
+    );
+    let mut args = vec!["component", "add", "--toolchain", name];
+    args.extend(&missing);
+    run("rustup", &args).with_context(|| {
+        format!(
+            "failed to install components, check the network connection \
+             or install them with `rustup component add --toolchain {name} {}`",
+            missing.join(" ")
+        )
+    })?;
+    Ok(())
+}
+
+impl Requirements {
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty() && self.min_rust_version.is_none()
+    }
+
+    /// Checks the toolchain version and installs the missing components.
+    /// The version of a pinned release is checked before anything is installed, so it fails offline.
+    pub fn ensure(&self, toolchain: &Toolchain, console: &Console) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        if let (Some(min_version), Some(version)) =
+            (&self.min_rust_version, toolchain.pinned_version())
+        {
+            check_min_version(toolchain, version, min_version)?;
+        }
+        match toolchain.rustup_name() {
+            Some(name) => {
+                ensure_installed(name, console)?;
+                if !self.components.is_empty() {
+                    ensure_components(name, &self.components, console)?;
+                }
+            }
+            None if !self.components.is_empty() => {
+                bail!("components can be installed only with a rustup toolchain, not \"empty\"")
+            }
+            None => {}
+        }
+        if let (Some(min_version), None) = (&self.min_rust_version, toolchain.pinned_version()) {
+            check_min_version(toolchain, &rustc_version(toolchain)?, min_version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(result: Result<()>) -> String {
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn min_version() {
+        let pinned = Toolchain::Pinned("1.79.0".to_string());
+        assert!(check_min_version(&pinned, "1.79.0", "1.79").is_ok());
+        assert!(check_min_version(&Toolchain::Stable, "1.83.0", "1.79.0").is_ok());
+
+        let err = error(check_min_version(&pinned, "1.79.0", "1.80.0"));
+        assert!(err.contains("pins an older toolchain"), "{err}");
+        let err = error(check_min_version(&Toolchain::Stable, "1.79.0", "1.80.0"));
+        assert!(err.contains("rustup update stable"), "{err}");
+        assert!(check_min_version(&Toolchain::Stable, "1.79.0", "latest").is_err());
+    }
+
+    #[test]
+    fn pinned_release_is_checked_before_installing() {
+        let requirements = Requirements {
+            components: vec!["llvm-tools".to_string()],
+            min_rust_version: Some("1.80.0".to_string()),
+        };
+        // The check fails without running rustup, even if the release is not installed.
+        let pinned = Toolchain::Pinned("1.0.0".to_string());
+        let err = error(requirements.ensure(&pinned, &Console::default()));
+        assert!(err.contains("requires Rust 1.80.0 or newer"), "{err}");
+    }
+
+    #[test]
+    fn empty_toolchain() {
+        assert!(Requirements::default()
+            .ensure(&Toolchain::Empty, &Console::default())
+            .is_ok());
+        let requirements = Requirements {
+            components: vec!["clippy".to_string()],
+            min_rust_version: None,
+        };
+        let err = error(requirements.ensure(&Toolchain::Empty, &Console::default()));
+        assert!(err.contains("only with a rustup toolchain"), "{err}");
+    }
+}
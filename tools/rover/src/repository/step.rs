@@ -1,8 +1,9 @@
 use super::command::Command;
+use anyhow::{bail, Result};
+use itertools::Itertools;
 
 #[derive(Debug)]
 pub struct Step {
-    #[allow(unused)]
     name: String,
     commands: Vec<Command>,
 }
@@ -12,7 +13,6 @@ impl Step {
         Self { name, commands }
     }
 
-    #[allow(unused)]
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -21,3 +21,65 @@ impl Step {
         self.commands.as_slice()
     }
 }
+
+/// Steps chosen on the command line with `--step` and `--skip-step`.
+#[derive(Clone, Debug, Default)]
+pub struct StepSelection {
+    only: Vec<String>,
+    skip: Vec<String>,
+}
+
+impl StepSelection {
+    pub fn new(only: Vec<String>, skip: Vec<String>) -> Self {
+        Self { only, skip }
+    }
+
+    pub fn contains(&self, step: &Step) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|name| name == step.name()))
+            && !self.skip.iter().any(|name| name == step.name())
+    }
+
+    /// Fails if a selected step is not among `known`, so that a typo doesn't silently skip everything.
+    pub fn check_names<'a>(&self, known: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let known: Vec<_> = known.into_iter().collect();
+        for name in self.only.iter().chain(&self.skip) {
+            if !known.contains(&name.as_str()) {
+                bail!(
+                    "unknown step \"{name}\", available steps are: {}",
+                    known.iter().map(|name| format!("\"{name}\"")).join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str) -> Step {
+        Step::new(name.to_string(), vec![])
+    }
+
+    #[test]
+    fn selection() {
+        let all = StepSelection::default();
+        assert!(all.contains(&step("linters")));
+
+        let only = StepSelection::new(vec!["testing".to_string()], vec![]);
+        assert!(only.contains(&step("testing")));
+        assert!(!only.contains(&step("linters")));
+
+        let skip = StepSelection::new(vec![], vec!["linters".to_string()]);
+        assert!(skip.contains(&step("testing")));
+        assert!(!skip.contains(&step("linters")));
+    }
+
+    #[test]
+    fn unknown_step_names() {
+        let selection = StepSelection::new(vec!["testing".to_string()], vec!["lint".to_string()]);
+        assert!(selection.check_names(["linters", "testing"]).is_err());
+        assert!(selection.check_names(["lint", "testing"]).is_ok());
+    }
+}
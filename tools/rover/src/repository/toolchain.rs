@@ -1,11 +1,17 @@
-use super::{command::Command, context::CommandContext};
+use super::{
+    bench_gate::run_bench_gate, command::Command, context::CommandContext,
+    coverage::run_cargo_coverage, libtest::run_cargo_test,
+};
+use crate::{
+    codestyle::{doc_coverage::check_doc_coverage, style::check_files},
+    console_println,
+};
 use anyhow::{bail, Context, Result};
-use colored::Colorize;
-use itertools::Itertools;
 use std::{
     collections::HashMap,
     fs::File,
     io::{BufRead, BufReader},
+    path::{Path, PathBuf},
     process,
 };
 
@@ -22,7 +28,7 @@ const FORBID_COLLECTIONS_PATTERNS: [&str; 8] = [
     "BinaryHeap",
 ];
 
-fn filtered_env() -> HashMap<String, String> {
+pub(super) fn filtered_env() -> HashMap<String, String> {
     std::env::vars()
         .filter(|&(ref k, ref _v)| {
             k == "TMP"
@@ -42,55 +48,35 @@ fn filtered_env() -> HashMap<String, String> {
         .collect()
 }
 
-macro_rules! launch {
-    ($toolchain: expr, $command: expr, $context: expr) => {{
-        let toolchain_shell_line = $toolchain.get_shell_line()?;
-        let command_shell_line = $command.get_shell_line()?;
-        let toolchain_shell_iter = toolchain_shell_line.split(' ');
-        let command_shell_iter = command_shell_line.split(' ');
-        let mut iter = toolchain_shell_iter.chain(command_shell_iter);
-        let mut cmd = if let Some(program) = iter.next() {
-            let mut cmd = process::Command::new(program);
-            cmd.current_dir($context.get_workdir())
-                .env_clear()
-                .envs(filtered_env());
-            cmd
-        } else {
-            bail!("toolchain and command are empty")
-        };
-        while let Some(arg) = iter.next() {
-            cmd.arg(arg);
-        }
-
-        println!(
-            "{:>12} `{program} {args}`{dir}",
-            "Executing".cyan().bold(),
-            program = cmd.get_program().to_string_lossy(),
-            args = cmd.get_args().map(|arg| arg.to_string_lossy()).join(" "),
-            dir = if let Some(dir) = cmd.get_current_dir() {
-                format!(" ({})", dir.to_string_lossy())
-            } else {
-                "".to_string()
-            },
-        );
-        let mut child = cmd
-            .stdout($command.cmd_stdout())
-            .spawn()
-            .context("Failed to execute command")?;
-        let cmd_status = $command.wait(&mut child)?;
-        if cmd_status.success() {
-            Ok(())
-        } else {
-            bail!($command.get_failure_error(cmd_status))
-        }
-    }};
-}
-
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Toolchain {
     Empty,
     Stable,
     Nightly,
+    /// A release like `1.79.0` or a dated channel like `nightly-2024-09-01`.
+    Pinned(String),
+}
+
+fn is_number(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_release(name: &str) -> bool {
+    let parts: Vec<_> = name.split('.').collect();
+    (2..=3).contains(&parts.len()) && parts.iter().all(|part| is_number(part))
+}
+
+fn is_dated_channel(name: &str) -> bool {
+    let Some((channel, date)) = name.split_once('-') else {
+        return false;
+    };
+    let date: Vec<_> = date.split('-').collect();
+    matches!(channel, "stable" | "beta" | "nightly")
+        && date.len() == 3
+        && date
+            .iter()
+            .zip([4, 2, 2])
+            .all(|(part, len)| part.len() == len && is_number(part))
 }
 
 impl Toolchain {
@@ -99,18 +85,118 @@ impl Toolchain {
             "empty" => Self::Empty,
             "stable" => Self::Stable,
             "nightly" => Self::Nightly,
-            name => bail!("toolchain \"{name}\" is not supported"),
+            name if is_release(name) || is_dated_channel(name) => Self::Pinned(name.to_string()),
+            name => bail!(
+                "toolchain \"{name}\" is not supported, use \"empty\", \"stable\", \"nightly\", \
+                 a release like \"1.79.0\" or a dated channel like \"nightly-2024-09-01\""
+            ),
         })
     }
 
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Empty => "empty",
+            Self::Stable => "stable",
+            Self::Nightly => "nightly",
+            Self::Pinned(name) => name,
+        }
+    }
+
+    /// Name of the toolchain for rustup, `None` for the empty one which uses the tools from `PATH`.
+    pub fn rustup_name(&self) -> Option<&str> {
+        match self {
+            Self::Empty => None,
+            _ => Some(self.name()),
+        }
+    }
+
+    /// Rust version known from the name alone, without asking rustup.
+    pub fn pinned_version(&self) -> Option<&str> {
+        match self {
+            Self::Pinned(name) if is_release(name) => Some(name),
+            _ => None,
+        }
+    }
+
     pub fn get_shell_line(&self) -> Result<String> {
-        Ok(match self {
-            Self::Empty => "".to_string(),
-            Self::Stable => "rustup run --install stable".to_string(),
-            Self::Nightly => "rustup run --install nightly".to_string(),
+        Ok(match self.rustup_name() {
+            None => "".to_string(),
+            Some(name) => format!("rustup run --install {name}"),
         })
     }
 
+    /// Process running the shell line with this toolchain in the problem directory.
+    pub fn build_command(
+        &self,
+        shell_line: &str,
+        context: &CommandContext,
+    ) -> Result<process::Command> {
+        let toolchain_shell_line = self.get_shell_line()?;
+        let mut iter = toolchain_shell_line
+            .split(' ')
+            .chain(shell_line.split(' '))
+            .filter(|part| !part.is_empty());
+        let Some(program) = iter.next() else {
+            bail!("toolchain and command are empty")
+        };
+        let mut cmd = process::Command::new(program);
+        cmd.current_dir(context.get_workdir())
+            .env_clear()
+            .envs(filtered_env())
+            .args(iter);
+        Ok(cmd)
+    }
+
+    /// Stdout of the shell line run with this toolchain, failing with its stderr.
+    pub fn output(&self, shell_line: &str, context: &CommandContext) -> Result<String> {
+        let output = self
+            .build_command(shell_line, context)?
+            .output()
+            .with_context(|| format!("failed to execute `{shell_line}`"))?;
+        if !output.status.success() {
+            bail!(
+                "`{shell_line}` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Target directory of the workspace and the name of the problem's package.
+    pub fn cargo_metadata(&self, context: &CommandContext) -> Result<(PathBuf, String)> {
+        let metadata: serde_json::Value = serde_json::from_str(
+            &self.output("cargo metadata --format-version 1 --no-deps", context)?,
+        )
+        .context("failed to parse cargo metadata")?;
+        let target_dir = metadata["target_directory"]
+            .as_str()
+            .context("cargo metadata has no target directory")?;
+        let package = metadata["packages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|package| {
+                package["manifest_path"]
+                    .as_str()
+                    .and_then(|path| Path::new(path).parent())
+                    == Some(context.get_workdir())
+            })
+            .and_then(|package| package["name"].as_str())
+            .context("cargo metadata has no package of the problem")?;
+        Ok((target_dir.into(), package.to_string()))
+    }
+
+    fn launch(&self, command: &Command, context: &CommandContext) -> Result<()> {
+        let mut cmd = self.build_command(&command.get_shell_line()?, context)?;
+        cmd.args(command.test_filter_args(context.test_filter()));
+        context.print_executing(&cmd);
+        if context.run(&mut cmd)?.success() {
+            Ok(())
+        } else {
+            bail!(command.get_failure_error())
+        }
+    }
+
     pub fn run_command(
         &self,
         command: &Command,
@@ -172,111 +258,138 @@ impl Toolchain {
             }
             Command::CargoCompileTestMiniFrunk => {
                 // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("compilation-fail-generic")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("compilation-fail-labelled")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("compilation-fail-transmogrify")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                println!("Compile tests passed, don't worry :)");
+                console_println!(context.console(), "Compile tests passed, don't worry :)");
                 Ok(())
             }
             Command::CargoCompileTestOrm => {
                 // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("test-lifetimes-create")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("test-lifetimes-get")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                println!("Compile tests passed, don't worry :)");
+                console_println!(context.console(), "Compile tests passed, don't worry :)");
                 Ok(())
             }
             Command::CargoCompileTestSnapshot => {
                 // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
+                let mut cmd = process::Command::new("cargo");
+                cmd.current_dir(context.get_workdir())
                     .arg("test")
                     .arg("--features")
                     .arg("test-lifetime")
                     .env_clear()
-                    .envs(filtered_env())
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
+                    .envs(filtered_env());
+                if context.run(&mut cmd)?.success() {
                     bail!("command failed")
                 }
-                println!("Compile tests passed, don't worry :)");
+                console_println!(context.console(), "Compile tests passed, don't worry :)");
                 Ok(())
             }
             Command::CargoFmt
             | Command::CargoClippy
-            | Command::CargoTest
-            | Command::CargoTestDebug
             | Command::PythonTest
-            | Command::CargoMiriTest => {
-                launch!(self, command, context)
-            }
-            Command::CargoTestValidate | Command::CargoTestDebugValidate => {
-                if !matches!(self, Self::Nightly) {
-                    bail!("Command {command:?} requires nightly toolchain");
-                }
-                launch!(self, command, context)
-            }
+            | Command::CargoMiriTest => self.launch(command, context),
+            Command::CargoTest => run_cargo_test(self, true, context),
+            Command::CargoTestDebug => run_cargo_test(self, false, context),
+            Command::CargoCoverage { threshold } => run_cargo_coverage(self, *threshold, context),
+            Command::CargoBenchGate {
+                baseline,
+                tolerance,
+            } => run_bench_gate(self, baseline, *tolerance, context),
+            Command::DocCoverage {
+                threshold,
+                examples_threshold,
+            } => check_doc_coverage(
+                context.get_user_files(),
+                context.get_workdir(),
+                *threshold,
+                *examples_threshold,
+                context.console(),
+            ),
+            Command::Codestyle { rules } => check_files(
+                context.get_user_files(),
+                context.get_workdir(),
+                rules,
+                context.console(),
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_toolchain_names() {
+        for name in ["1.79", "1.79.0", "nightly-2024-09-01", "stable-2024-06-13"] {
+            assert_eq!(Toolchain::from_name(name).unwrap().name(), name);
+        }
+        for name in [
+            "1",
+            "1.x.0",
+            "1.79.0.1",
+            "nightly-2024-9-1",
+            "custom-2024-09-01",
+        ] {
+            assert!(Toolchain::from_name(name).is_err(), "{name}");
+        }
+        assert_eq!(
+            Toolchain::from_name("1.79.0").unwrap().pinned_version(),
+            Some("1.79.0")
+        );
+        assert_eq!(
+            Toolchain::from_name("nightly-2024-09-01")
+                .unwrap()
+                .pinned_version(),
+            None
+        );
+        assert_eq!(Toolchain::Empty.rustup_name(), None);
+    }
+}
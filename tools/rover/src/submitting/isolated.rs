@@ -0,0 +1,157 @@
+use super::submit::print_push_summary;
+use crate::{
+    git::{GitError, GitRepository, DEFAULT_REMOTE as REMOTE},
+    repository::problem::Problem,
+};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::path::Path;
+
+/// Pushes a commit containing only the problem's allowed files on top of the remote problem branch.
+///
+/// If the problem branch does not exist yet, the commit is based on the remote default branch.
+/// The push is not forced, so it fails if the remote branch moved in the meantime.
+pub fn push_isolated_commit(git: &GitRepository, problem: &Problem, message: &str) -> Result<()> {
+    let branch = problem.branch_name();
+    let config = problem.config()?;
+    let files: Vec<_> = config
+        .get_relative_user_files()
+        .iter()
+        .map(|file| problem.relative_path().join(file))
+        .collect();
+    if files.is_empty() {
+        bail!("problem {branch} has no files matching allowed patterns");
+    }
+
+    let parent = if git.list_remote(REMOTE)?.get(&branch).is_some() {
+        git.fetch_branch(REMOTE, &branch)?
+    } else {
+        println!(
+            "{:>12} branch {branch} does not exist on the remote, creating it",
+            "Note".cyan().bold()
+        );
+        git.fetch_default_branch(REMOTE)
+            .context("failed to fetch the default branch of the remote")?
+    };
+
+    // Files deleted or renamed since the last submission must not be tested again.
+    let problem_dir = problem.relative_path();
+    let is_user_file = |path: &Path| {
+        path.strip_prefix(&problem_dir)
+            .is_ok_and(|file| config.matches_user_file(&problem.path().join(file)))
+    };
+    let tree = git
+        .tree_with_files(parent, &files, is_user_file)
+        .context("failed to build the tree of the submission")?;
+    let commit = git.commit_tree(tree, parent, message)?;
+    print_push_summary(git, &format!("{REMOTE}/{branch}"), Some(parent), commit)?;
+    match git.push(REMOTE, &format!("{commit}:refs/heads/{branch}")) {
+        Ok(()) => {}
+        Err(err @ GitError::PushRejected { .. }) => {
+            return Err(err).context(format!(
+                "branch {branch} on the remote has moved since it was fetched, try to submit again"
+            ))
+        }
+        Err(err) => return Err(err).context("git push failed"),
+    }
+    println!(
+        "{:>12} {} to {REMOTE}/{branch}",
+        "Pushed".green().bold(),
+        &commit.to_string()[..12],
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{course::COURSE_CONFIG, problem::DEFAULT_YML_NAME, repo::Repository};
+    use std::fs;
+    use tempfile::TempDir;
+
+    const COURSE: &str = r#"
+        name = "test"
+        [endpoints]
+        course = "https://example.com"
+        scoreboard = "https://example.com/scoreboard"
+        actions = "https://example.com/actions"
+        report = "https://example.com/api/report"
+    "#;
+    const PROBLEM_CONFIG: &str = "toolchain: stable\nallowed-patterns: [src/*.rs]\nsteps: {}\n";
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn remote_files(remote: &Path, branch: &str) -> Vec<String> {
+        let repo = git2::Repository::open_bare(remote).unwrap();
+        let tree = repo
+            .find_reference(&format!("refs/heads/{branch}"))
+            .unwrap()
+            .peel_to_tree()
+            .unwrap();
+        let mut files = vec![];
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                files.push(format!("{dir}{}", entry.name().unwrap()));
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .unwrap();
+        files
+    }
+
+    #[test]
+    fn deleted_files_are_removed_from_the_submission() {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        git2::Repository::init_bare(&remote)
+            .unwrap()
+            .set_head("refs/heads/main")
+            .unwrap();
+        let work = dir.path().join("work");
+        let repo = git2::Repository::init(&work).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Rover").unwrap();
+        config.set_str("user.email", "rover@example.com").unwrap();
+        repo.remote(REMOTE, remote.to_str().unwrap()).unwrap();
+
+        let problem_dir = work.join("problems/g/t");
+        write(&work.join(COURSE_CONFIG), COURSE);
+        write(&problem_dir.join(DEFAULT_YML_NAME), PROBLEM_CONFIG);
+        write(&problem_dir.join("src/lib.rs"), "mod old;\n");
+        write(&problem_dir.join("src/old.rs"), "old\n");
+        write(&work.join("problems/g/other/src/lib.rs"), "other\n");
+        let git = GitRepository::open(&work).unwrap();
+        git.add(&work).unwrap();
+        git.commit("course").unwrap();
+        git.push(REMOTE, "HEAD:refs/heads/main").unwrap();
+
+        let course = Repository::from_path(&problem_dir).unwrap();
+        let problem = || {
+            course
+                .problems()
+                .unwrap()
+                .into_iter()
+                .find(|problem| problem.title() == "t")
+                .unwrap()
+        };
+        push_isolated_commit(&git, &problem(), "first").unwrap();
+        let branch = problem().branch_name();
+        assert!(remote_files(&remote, &branch).contains(&"problems/g/t/src/old.rs".to_string()));
+
+        fs::rename(
+            problem_dir.join("src/old.rs"),
+            problem_dir.join("src/new.rs"),
+        )
+        .unwrap();
+        push_isolated_commit(&git, &problem(), "second").unwrap();
+        let files = remote_files(&remote, &branch);
+        assert!(files.contains(&"problems/g/t/src/new.rs".to_string()));
+        assert!(!files.contains(&"problems/g/t/src/old.rs".to_string()));
+        // Files outside of the allowed patterns stay as they are on the remote.
+        assert!(files.contains(&"problems/g/t/.config.yml".to_string()));
+        assert!(files.contains(&"problems/g/other/src/lib.rs".to_string()));
+    }
+}
@@ -1 +1,2 @@
+mod isolated;
 pub(crate) mod submit;
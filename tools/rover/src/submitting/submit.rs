@@ -1,42 +1,133 @@
-use crate::{launch_git, repository::repo::Repository};
-use anyhow::{bail, Result};
+use super::isolated::push_isolated_commit;
+use crate::{
+    git::{GitRepository, DEFAULT_REMOTE as REMOTE},
+    repository::{
+        context::LaunchOptions,
+        problem::{Problem, LINT_STEP_NAME},
+        repo::Repository,
+    },
+};
+use anyhow::{Context, Result};
 use colored::Colorize;
+use git2::Oid;
 use indoc::printdoc;
-use std::{path::Path, process};
+use std::path::Path;
 
-const MANYTASK_URL: &str = "https://rust-hse-2024.azazkamaz.me";
-const SCOREBOARD_URL: &str = "https://rust-hse-2024.azazkamaz.me/scoreboard";
-const ACTIONS_URL: &str = "https://rust-hse-2024.azazkamaz.me/repo/actions";
+/// Whether to run pre-flight checks before submitting.
+/// `Default` takes the value of `submit-check` from the problem's `.config.yml`.
+#[derive(Clone, Copy, Debug)]
+pub enum SubmitCheck {
+    Default,
+    Enabled,
+    Disabled,
+}
+
+fn run_checks(git: &GitRepository, problem: &Problem) -> Result<()> {
+    let problem_path = problem.relative_path();
+    let outside: Vec<_> = git
+        .changed_paths()?
+        .into_iter()
+        .filter(|path| !path.starts_with(&problem_path))
+        .collect();
+    if !outside.is_empty() {
+        println!(
+            "{}: uncommitted changes outside of {} will not be checked:",
+            "warning".yellow().bold(),
+            problem.relative_path().display(),
+        );
+        for path in outside {
+            println!("  {}", path.display());
+        }
+    }
+
+    println!(
+        "{:>12} {}",
+        "Checking".green().bold(),
+        problem.branch_name()
+    );
+    problem
+        .launch_steps(
+            |step| step.name() == LINT_STEP_NAME,
+            &LaunchOptions::default(),
+        )
+        .context("pre-submit checks failed, refusing to submit")
+}
+
+/// Prints what pushing `commit` to `target` changes, `base` is what the remote has there now.
+pub(super) fn print_push_summary(
+    git: &GitRepository,
+    target: &str,
+    base: Option<Oid>,
+    commit: Oid,
+) -> Result<()> {
+    println!("{:>12} to {target}:", "Submitting".green().bold());
+    print!("{}", git.push_summary(base, commit)?);
+    Ok(())
+}
 
-pub fn submit_problem(problem_path: &Path, message: &str) -> Result<()> {
+pub struct SubmitOptions<'a> {
+    pub message: &'a str,
+    pub check: SubmitCheck,
+    /// Push a commit with only the problem's allowed files instead of force-pushing `HEAD`.
+    pub isolated: bool,
+}
+
+fn push_head(git: &GitRepository, problem: &Problem, message: &str, check: bool) -> Result<()> {
+    git.add(&problem.relative_path())
+        .context("git add failed")?;
+    let commit = git.commit(message).context("git commit failed")?;
+    if check {
+        let branch = problem.branch_name();
+        match git.list_remote(REMOTE)?.get(&branch) {
+            Some(_) => {
+                let remote = git.fetch_branch(REMOTE, &branch)?;
+                print_push_summary(git, &format!("{REMOTE}/{branch}"), Some(remote), commit)?;
+            }
+            None => print_push_summary(
+                git,
+                &format!("the new branch {REMOTE}/{branch}"),
+                None,
+                commit,
+            )?,
+        }
+    }
+    git.push(
+        REMOTE,
+        &format!("+HEAD:refs/heads/{}", problem.branch_name()),
+    )
+    .context("git push failed")?;
+    Ok(())
+}
+
+pub fn submit_problem(problem_path: &Path, options: &SubmitOptions) -> Result<()> {
     let repo = Repository::from_path(problem_path)?;
     let problem = repo.problem_from_path(problem_path)?;
-    let repo_path = repo.get_path();
-    if !launch_git!(repo_path, "add", problem.relative_path()) {
-        bail!("git add failed");
-    }
-    if !launch_git!(repo_path, "commit", "-m", message, "--allow-empty") {
-        bail!("git commit failed: either no changes since the last commit or git failed")
+    let git = GitRepository::open(repo.get_path())?;
+    let check = match options.check {
+        SubmitCheck::Default => problem.config()?.get_submit_check(),
+        SubmitCheck::Enabled => true,
+        SubmitCheck::Disabled => false,
+    };
+    if check {
+        run_checks(&git, &problem)?;
     }
-    if !launch_git!(
-        repo_path,
-        "push",
-        "--force",
-        "origin",
-        &format!("HEAD:{}", problem.branch_name())
-    ) {
-        bail!("git push failed")
+    if options.isolated {
+        push_isolated_commit(&git, &problem, options.message)?;
+    } else {
+        push_head(&git, &problem, options.message, check)?;
     }
+    let endpoints = repo.course().endpoints();
     printdoc! {"
         Check results here:   {actions_url}
 
-        Other useful links:
+        Other useful links for {course_name}:
           Course page:        {course_url}
           Scoreboard sheet:   {scoreboard_url}
         ",
-        actions_url = ACTIONS_URL.bold(),
-        course_url = MANYTASK_URL.bold(),
-        scoreboard_url = SCOREBOARD_URL.bold(),
+        course_name = repo.course().name(),
+        actions_url = endpoints.actions.bold(),
+        course_url = endpoints.course.bold(),
+        scoreboard_url = endpoints.scoreboard.bold(),
     };
     Ok(())
 }
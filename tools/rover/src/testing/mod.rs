@@ -1,2 +1,4 @@
 pub mod report;
 pub mod test;
+pub mod test_all;
+pub mod watch;
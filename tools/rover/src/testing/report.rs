@@ -1,8 +1,8 @@
+use crate::repository::course::Course;
 use anyhow::{bail, Context, Result};
 use reqwest::blocking::{multipart::Form, Client};
 use std::{env, io::Read, thread, time};
 
-const MANYTASK_URL: &str = "https://rust-hse-2024.azazkamaz.me/api/report";
 const MANYTASK_RETRIES: usize = 3;
 
 pub enum ReportType {
@@ -19,7 +19,7 @@ impl ReportType {
         })
     }
 
-    pub fn push_report(&self, failed: bool) -> Result<()> {
+    pub fn push_report(&self, failed: bool, course: &Course) -> Result<()> {
         match self {
             Self::NoReport => {
                 if failed {
@@ -32,12 +32,10 @@ impl ReportType {
                 if env::var("SKIP_REPORT").is_ok() {
                     return Ok(());
                 }
-                let task_name = env::var("GITHUB_REF_NAME")
-                    .context("no GITHUB_REF_NAME variable")?
-                    .split('/')
-                    .nth(1)
-                    .context("GITHUB_REF_NAME does not contain '/' symbol")?
-                    .to_owned();
+                let branch = env::var("GITHUB_REF_NAME").context("no GITHUB_REF_NAME variable")?;
+                let (_, task_name) = course.branch_scheme().parse(&branch).with_context(|| {
+                    format!("GITHUB_REF_NAME \"{branch}\" does not match the branch scheme")
+                })?;
                 let user_id = env::var("GITHUB_ACTOR_ID").context("no GITLAB_USER_ID variable")?;
                 let tester_token = env::var("TESTER_TOKEN").context("no TESTER_TOKEN variable")?;
                 let client = Client::new();
@@ -49,7 +47,7 @@ impl ReportType {
                         data = data.text("failed", "1");
                     }
                     let mut response = client
-                        .post(MANYTASK_URL)
+                        .post(&course.endpoints().report)
                         .multipart(data)
                         .header("Authorization", tester_token.clone())
                         .send()
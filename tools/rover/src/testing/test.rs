@@ -1,6 +1,12 @@
-use crate::repository::problem::Problem;
+use crate::repository::{context::LaunchOptions, problem::Problem, step::StepSelection};
 use anyhow::Result;
 
-pub fn test_problem(problem: Problem) -> Result<()> {
-    problem.launch_all_steps()
+pub fn test_problem(
+    problem: &Problem,
+    selection: &StepSelection,
+    options: &LaunchOptions,
+) -> Result<()> {
+    let config = problem.config()?;
+    selection.check_names(config.get_steps().iter().map(|step| step.name()))?;
+    problem.launch_steps(|step| selection.contains(step), options)
 }
@@ -0,0 +1,288 @@
+use crate::repository::{
+    console::Console,
+    context::LaunchOptions,
+    problem::{Problem, StepReport},
+    repo::Repository,
+    step::StepSelection,
+};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use itertools::Itertools;
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+const CELL_WIDTH: usize = 6;
+
+struct ProblemOutcome {
+    name: String,
+    steps: Result<Vec<StepReport>>,
+}
+
+impl ProblemOutcome {
+    fn print_errors(&self) {
+        match &self.steps {
+            Ok(steps) => {
+                for step in steps {
+                    if let Some(Err(err)) = &step.result {
+                        println!("{}: step {}: {err:#}", "error".red().bold(), step.name);
+                    }
+                }
+            }
+            Err(err) => println!("{}: {err:#}", "error".red().bold()),
+        }
+    }
+
+    fn passed(&self) -> bool {
+        match &self.steps {
+            Ok(steps) => steps.iter().all(|step| matches!(step.result, Some(Ok(())))),
+            Err(_) => false,
+        }
+    }
+}
+
+fn problem_name(problem: &Problem) -> String {
+    format!("{}/{}", problem.group(), problem.title())
+}
+
+fn test_sequentially(
+    problems: &[Problem],
+    selection: &StepSelection,
+    options: &LaunchOptions,
+) -> Vec<ProblemOutcome> {
+    problems
+        .iter()
+        .map(|problem| {
+            println!("{:>12} {}", "Testing".cyan().bold(), problem_name(problem));
+            let outcome = ProblemOutcome {
+                name: problem_name(problem),
+                steps: problem.run_steps(|step| selection.contains(step), options),
+            };
+            outcome.print_errors();
+            outcome
+        })
+        .collect()
+}
+
+/// Tests problems on `jobs` threads, printing the captured output of failed problems as they finish.
+fn test_in_parallel(
+    problems: &[Problem],
+    jobs: usize,
+    selection: &StepSelection,
+    options: &LaunchOptions,
+) -> Vec<ProblemOutcome> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::with_capacity(problems.len()));
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(problem) = problems.get(i) else {
+                    break;
+                };
+                let (steps, output) = match Console::captured() {
+                    Ok(console) => {
+                        let options = LaunchOptions {
+                            console,
+                            ..options.clone()
+                        };
+                        (
+                            problem.run_steps(|step| selection.contains(step), &options),
+                            options.console.captured_output(),
+                        )
+                    }
+                    Err(err) => (Err(err), String::new()),
+                };
+                let outcome = ProblemOutcome {
+                    name: problem_name(problem),
+                    steps,
+                };
+
+                let mut outcomes = outcomes.lock().unwrap();
+                if outcome.passed() {
+                    println!("{:>12} {}", "Passed".green().bold(), outcome.name);
+                } else {
+                    println!("{:>12} {}", "Failed".red().bold(), outcome.name);
+                    print!("{output}");
+                    outcome.print_errors();
+                }
+                outcomes.push((i, outcome));
+            });
+        }
+    });
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by_key(|(i, _)| *i);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+fn print_summary(outcomes: &[ProblemOutcome]) {
+    let mut step_names: Vec<&str> = vec![];
+    for steps in outcomes
+        .iter()
+        .filter_map(|outcome| outcome.steps.as_ref().ok())
+    {
+        for step in steps {
+            if !step_names.contains(&step.name.as_str()) {
+                step_names.push(&step.name);
+            }
+        }
+    }
+    let name_width = outcomes
+        .iter()
+        .map(|outcome| outcome.name.len())
+        .max()
+        .unwrap_or(0);
+    let cell_width = step_names
+        .iter()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(CELL_WIDTH);
+
+    println!("\n{}", "Summary".bold());
+    print!("{:name_width$}", "");
+    for name in &step_names {
+        print!("  {name:<cell_width$}");
+    }
+    println!();
+    for outcome in outcomes {
+        print!("{:name_width$}", outcome.name);
+        match &outcome.steps {
+            Ok(steps) => {
+                for name in &step_names {
+                    let cell = match steps.iter().find(|step| step.name == *name) {
+                        Some(StepReport {
+                            result: Some(Ok(())),
+                            ..
+                        }) => format!("{:cell_width$}", "ok").green(),
+                        Some(StepReport {
+                            result: Some(Err(_)),
+                            ..
+                        }) => format!("{:cell_width$}", "FAIL").red().bold(),
+                        Some(StepReport { result: None, .. }) => {
+                            format!("{:cell_width$}", "skip").yellow()
+                        }
+                        None => format!("{:cell_width$}", "-").dimmed(),
+                    };
+                    print!("  {cell}");
+                }
+            }
+            Err(err) => print!("  {}", format!("error: {err:#}").red()),
+        }
+        println!();
+    }
+}
+
+/// Problems of the course, or only the problems of `group`. Fails if there are none.
+fn select_problems(repo: &Repository, group: Option<&str>) -> Result<Vec<Problem>> {
+    let mut problems = repo.problems()?;
+    if let Some(group) = group {
+        problems.retain(|problem| problem.group() == group);
+    }
+    if problems.is_empty() {
+        match group {
+            Some(group) => bail!("no problems found in group \"{group}\""),
+            None => bail!("no problems found"),
+        }
+    }
+    Ok(problems)
+}
+
+/// Tests every problem of the course, or only the problems of `group`, and prints a summary
+/// of the steps' results. Unlike testing a single problem, steps of the other problems keep
+/// running after a failure.
+pub fn test_all_problems(
+    path: &Path,
+    group: Option<&str>,
+    jobs: usize,
+    selection: &StepSelection,
+    options: &LaunchOptions,
+) -> Result<()> {
+    let repo = Repository::from_path(path)?;
+    let problems = select_problems(&repo, group)?;
+
+    let configs: Vec<_> = problems
+        .iter()
+        .filter_map(|problem| problem.config().ok())
+        .collect();
+    selection.check_names(
+        configs
+            .iter()
+            .flat_map(|config| config.get_steps())
+            .map(|step| step.name())
+            .unique(),
+    )?;
+
+    let jobs = jobs.min(problems.len());
+    let outcomes = if jobs > 1 {
+        // Workers installing the same toolchain at once would race in rustup.
+        Problem::ensure_requirements(&problems, options)?;
+        let options = LaunchOptions {
+            requirements_ensured: true,
+            ..options.clone()
+        };
+        test_in_parallel(&problems, jobs, selection, &options)
+    } else {
+        test_sequentially(&problems, selection, options)
+    };
+    print_summary(&outcomes);
+
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+    if failed > 0 {
+        bail!("{failed} of {} problems failed", outcomes.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{course::COURSE_CONFIG, problem::DEFAULT_YML_NAME};
+    use std::fs;
+
+    const COURSE: &str = r#"
+        name = "test"
+        [endpoints]
+        course = "https://example.com"
+        scoreboard = "https://example.com/scoreboard"
+        actions = "https://example.com/actions"
+        report = "https://example.com/api/report"
+    "#;
+
+    fn names(problems: &[Problem]) -> Vec<String> {
+        problems.iter().map(problem_name).collect()
+    }
+
+    #[test]
+    fn discovery_and_group_filter() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join(COURSE_CONFIG), COURSE).unwrap();
+        for problem in ["structs/lru", "basics/add", "basics/sum", "basics/draft"] {
+            fs::create_dir_all(dir.path().join("problems").join(problem)).unwrap();
+        }
+        for problem in ["structs/lru", "basics/add", "basics/sum"] {
+            let config = dir
+                .path()
+                .join("problems")
+                .join(problem)
+                .join(DEFAULT_YML_NAME);
+            fs::write(config, "").unwrap();
+        }
+
+        let repo = Repository::from_path(&dir.path().join("problems/basics")).unwrap();
+        assert_eq!(
+            names(&select_problems(&repo, None).unwrap()),
+            ["basics/add", "basics/sum", "structs/lru"]
+        );
+        assert_eq!(
+            names(&select_problems(&repo, Some("basics")).unwrap()),
+            ["basics/add", "basics/sum"]
+        );
+        assert!(select_problems(&repo, Some("missing")).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use super::test::test_problem;
+use crate::repository::{
+    context::{Cancellation, LaunchOptions},
+    problem::{Problem, DEFAULT_YML_NAME},
+    step::StepSelection,
+};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Editors often save a file as a burst of events, so a run starts only after this much quiet.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// A burst of file events, which settles once no event came for [`DEBOUNCE`].
+struct Debounce {
+    last: Instant,
+}
+
+impl Debounce {
+    fn new(first: Instant) -> Self {
+        Self { last: first }
+    }
+
+    fn deadline(&self) -> Instant {
+        self.last + DEBOUNCE
+    }
+
+    fn is_settled(&self, now: Instant) -> bool {
+        now >= self.deadline()
+    }
+
+    /// Adds an event to the burst, unless the burst had settled before it came.
+    fn add(&mut self, at: Instant) -> bool {
+        if self.is_settled(at) {
+            return false;
+        }
+        self.last = self.last.max(at);
+        true
+    }
+}
+
+fn run_once(problem: &Problem, selection: &StepSelection, options: &LaunchOptions) {
+    println!("{:>12} {}", "Testing".cyan().bold(), problem.branch_name());
+    let result = test_problem(problem, selection, options);
+    if options.cancellation.is_cancelled() {
+        return;
+    }
+    match result {
+        Ok(_) => println!("{:>12} {}", "Passed".green().bold(), problem.branch_name()),
+        Err(err) => println!("{}: {err:?}", "Error".red().bold()),
+    }
+    println!(
+        "{:>12} for changes, press Ctrl-C to stop",
+        "Waiting".cyan().bold()
+    );
+}
+
+fn is_relevant(event: &Event, problem: &Problem) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    let config = problem.config().ok();
+    event.paths.iter().any(|path| {
+        path.ends_with(DEFAULT_YML_NAME)
+            || config
+                .as_ref()
+                .is_some_and(|config| config.matches_user_file(path))
+    })
+}
+
+fn wait_for_changes(receiver: &Receiver<notify::Result<Event>>, problem: &Problem) -> Result<()> {
+    loop {
+        match receiver.recv().context("file watcher has stopped")? {
+            Ok(event) if is_relevant(&event, problem) => break,
+            Ok(_) => {}
+            Err(err) => println!("{}: file watcher: {err}", "warning".yellow().bold()),
+        }
+    }
+    let mut debounce = Debounce::new(Instant::now());
+    loop {
+        let now = Instant::now();
+        if debounce.is_settled(now) {
+            return Ok(());
+        }
+        // Only relevant events extend the burst, `target/` or swap files may keep changing.
+        match receiver.recv_timeout(debounce.deadline() - now) {
+            Ok(Ok(event)) if is_relevant(&event, problem) => {
+                if !debounce.add(Instant::now()) {
+                    return Ok(());
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => println!("{}: file watcher: {err}", "warning".yellow().bold()),
+            Err(RecvTimeoutError::Timeout) => return Ok(()),
+            Err(RecvTimeoutError::Disconnected) => bail!("file watcher has stopped"),
+        }
+    }
+}
+
+/// Runs the problem's steps every time a file matching `allowed-patterns` or the config changes.
+/// A run that is still going when a change comes is cancelled.
+pub fn watch_problem(
+    problem: &Problem,
+    selection: &StepSelection,
+    options: &LaunchOptions,
+) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(sender).context("failed to create file watcher")?;
+    watcher
+        .watch(problem.path(), RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {:?}", problem.path()))?;
+
+    thread::scope(|scope| loop {
+        let options = LaunchOptions {
+            cancellation: Cancellation::default(),
+            ..options.clone()
+        };
+        let cancellation = options.cancellation.clone();
+        let run = scope.spawn(move || run_once(problem, selection, &options));
+        wait_for_changes(&receiver, problem)?;
+        if !run.is_finished() {
+            println!("{:>12} the current run", "Cancelling".yellow().bold());
+            cancellation.cancel();
+        }
+        let _ = run.join();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{course::COURSE_CONFIG, repo::Repository};
+    use notify::event::ModifyKind;
+    use std::{fs, path::Path};
+
+    const COURSE: &str = r#"
+        name = "test"
+        [endpoints]
+        course = "https://example.com"
+        scoreboard = "https://example.com/scoreboard"
+        actions = "https://example.com/actions"
+        report = "https://example.com/api/report"
+    "#;
+
+    fn modified(path: &Path) -> notify::Result<Event> {
+        Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.to_path_buf()))
+    }
+
+    #[test]
+    fn debounce() {
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        let mut debounce = Debounce::new(start);
+        assert!(!debounce.is_settled(start + ms(299)));
+
+        // Events closer than `DEBOUNCE` to the previous one extend the burst.
+        assert!(debounce.add(start + ms(100)));
+        assert!(debounce.add(start + ms(350)));
+        assert_eq!(debounce.deadline(), start + ms(650));
+        assert!(!debounce.is_settled(start + ms(649)));
+        assert!(debounce.is_settled(start + ms(650)));
+
+        // An event after the burst settled starts the next run instead.
+        assert!(!debounce.add(start + ms(700)));
+        assert_eq!(debounce.deadline(), start + ms(650));
+    }
+
+    #[test]
+    fn only_relevant_events_extend_the_burst() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let problem_dir = dir.path().join("problems/g/t");
+        fs::create_dir_all(problem_dir.join("src")).unwrap();
+        fs::write(dir.path().join(COURSE_CONFIG), COURSE).unwrap();
+        fs::write(
+            problem_dir.join(DEFAULT_YML_NAME),
+            "toolchain: stable\nallowed-patterns: [src/*.rs]\nsteps: {}\n",
+        )
+        .unwrap();
+        let problem = Repository::from_path(&problem_dir)
+            .unwrap()
+            .problems()
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        sender
+            .send(modified(&problem_dir.join("src/lib.rs")))
+            .unwrap();
+        let started = Instant::now();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                // Builds keep writing to `target/` long after the solution was saved.
+                for _ in 0..20 {
+                    thread::sleep(Duration::from_millis(50));
+                    let _ = sender.send(modified(&problem_dir.join("target/debug/build.log")));
+                    let _ = sender.send(Err(notify::Error::generic("overflow")));
+                }
+            });
+            wait_for_changes(&receiver, &problem).unwrap();
+            assert!(started.elapsed() < Duration::from_millis(600));
+        });
+    }
+}
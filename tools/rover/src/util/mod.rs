@@ -1,3 +1,2 @@
 #[cfg(feature = "check-version")]
 pub(crate) mod check_version;
-pub(crate) mod util_macro;
@@ -0,0 +1,112 @@
+//! Submission of the tasks listed in a manifest:
+//!
+//! ```yaml
+//! - type: python
+//!   file: scripts/hello.py
+//!   args: "first second"
+//! - type: bin
+//!   file: build/solver
+//!   argv: ["with spaces", ""]
+//!   stdin: input.txt
+//!   env: {MODE: fast}
+//! ```
+
+use crate::{
+    error::ClientError,
+    print_json,
+    task::{Negotiator, TaskInput},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, process::ExitCode};
+use task_solver_protocol::{blocking::Client, Status, StatusResponse, TaskType};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    r#type: TaskType,
+    file: String,
+    #[serde(default)]
+    args: String,
+    #[serde(default)]
+    argv: Vec<String>,
+    stdin: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct CreatedTask<'a> {
+    id: &'a str,
+    file: &'a str,
+}
+
+#[derive(Serialize)]
+struct FinishedTask<'a> {
+    id: &'a str,
+    file: &'a str,
+    #[serde(flatten)]
+    status: StatusResponse,
+}
+
+fn read_manifest(manifest: &str) -> Result<Vec<Entry>, ClientError> {
+    let content = fs::read_to_string(manifest).map_err(|source| ClientError::FileRead {
+        path: manifest.to_string(),
+        source,
+    })?;
+    serde_yaml::from_str(&content).map_err(|source| ClientError::Manifest {
+        path: manifest.to_string(),
+        source,
+    })
+}
+
+/// Creates the tasks of the manifest and prints a JSON line with the id of each, or with its
+/// final status if `wait` is set. All the files are read before the first task is created.
+pub fn run(client: &Client, manifest: &str, wait: bool) -> Result<ExitCode, ClientError> {
+    let entries = read_manifest(manifest)?;
+    let directory = Path::new(manifest).parent().unwrap_or(Path::new(""));
+    let relative = |path: &str| directory.join(path).to_string_lossy().into_owned();
+    let mut negotiator = Negotiator::new(client);
+    let requests = entries
+        .iter()
+        .map(|entry| {
+            let input = TaskInput {
+                args: entry.args.clone(),
+                argv: entry.argv.clone(),
+                stdin: entry.stdin.as_deref().map(relative),
+                env: entry.env.clone(),
+            };
+            negotiator.build_request(&entry.r#type, &relative(&entry.file), &input)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut ids = Vec::with_capacity(requests.len());
+    for (entry, request) in entries.iter().zip(&requests) {
+        let id = client.create_task(request)?;
+        if !wait {
+            print_json(&CreatedTask {
+                id: &id,
+                file: &entry.file,
+            });
+        }
+        ids.push(id);
+    }
+    if !wait {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut failed = false;
+    for (entry, id) in entries.iter().zip(&ids) {
+        let status = client.wait(id)?;
+        failed |= status.status != Status::Success;
+        print_json(&FinishedTask {
+            id,
+            file: &entry.file,
+            status,
+        });
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
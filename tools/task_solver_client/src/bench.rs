@@ -0,0 +1,151 @@
+//! Load generation: submits many tasks at once and reports how the server handled them.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+use task_solver_protocol::{
+    blocking::Client, parse_time, CreateTaskRequest, Status, StatusResponse,
+};
+
+/// Short enough for the wall time to be close to the time the server took.
+const MAX_POLL_DELAY: Duration = Duration::from_millis(100);
+const PERCENTILES: [usize; 3] = [50, 90, 99];
+
+pub struct Load {
+    pub tasks: usize,
+    pub concurrency: usize,
+    /// Request sent for every task.
+    pub request: CreateTaskRequest,
+}
+
+/// Seconds the task waited in the queue and ran.
+struct Timings {
+    queue: f64,
+    run: f64,
+}
+
+fn timings(status: &StatusResponse) -> Result<Timings, String> {
+    let meta = &status.meta;
+    let created = parse_time(&meta.created_at)?;
+    let started = parse_time(
+        meta.started_at
+            .as_deref()
+            .ok_or("finished task has no started_at")?,
+    )?;
+    let finished = parse_time(
+        meta.finished_at
+            .as_deref()
+            .ok_or("finished task has no finished_at")?,
+    )?;
+    Ok(Timings {
+        queue: (started - created).as_seconds_f64(),
+        run: (finished - started).as_seconds_f64(),
+    })
+}
+
+/// Creates a task and polls it until it finishes.
+fn run_task(client: &Client, load: &Load) -> Result<StatusResponse, String> {
+    let id = client
+        .create_task(&load.request)
+        .map_err(|err| format!("failed to create a task: {err}"))?;
+    client
+        .wait_with_max_delay(&id, MAX_POLL_DELAY)
+        .map_err(|err| format!("failed to poll task {id}: {err}"))
+}
+
+/// Nearest-rank percentile of the sorted values.
+fn percentile(sorted: &[f64], percent: usize) -> f64 {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn print_distribution(name: &str, mut values: Vec<f64>) {
+    values.sort_by(f64::total_cmp);
+    let percentiles: Vec<_> = PERCENTILES
+        .iter()
+        .map(|&percent| format!("p{percent} {:.2}s", percentile(&values, percent)))
+        .collect();
+    println!(
+        "{name}: {}, max {:.2}s",
+        percentiles.join(", "),
+        values[values.len() - 1]
+    );
+}
+
+/// Submits `load.tasks` tasks from `load.concurrency` threads, waits for all of them and prints
+/// the throughput and the queue and run time percentiles. Returns whether every task was tracked.
+pub fn run(client: &Client, load: &Load) -> bool {
+    let submitted = AtomicUsize::new(0);
+    let started = Instant::now();
+    let results: Vec<Result<StatusResponse, String>> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..load.concurrency)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    while submitted.fetch_add(1, Ordering::Relaxed) < load.tasks {
+                        results.push(run_task(client, load));
+                    }
+                    results
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect()
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut finished = Vec::new();
+    let mut lost = 0;
+    for result in results {
+        match result.and_then(|status| Ok((timings(&status)?, status.status))) {
+            Ok(task) => finished.push(task),
+            Err(err) => {
+                lost += 1;
+                eprintln!("{err}");
+            }
+        }
+    }
+    let succeeded = finished
+        .iter()
+        .filter(|(_, status)| *status == Status::Success)
+        .count();
+    println!(
+        "Tasks: {} ({succeeded} succeeded, {} failed, {lost} lost) in {elapsed:.2}s, {:.2} tasks/s",
+        load.tasks,
+        finished.len() - succeeded,
+        finished.len() as f64 / elapsed
+    );
+    if finished.is_empty() {
+        return false;
+    }
+    let total_run: f64 = finished.iter().map(|(timings, _)| timings.run).sum();
+    println!("Average parallelism: {:.2}", total_run / elapsed);
+    print_distribution(
+        "Queue time",
+        finished.iter().map(|(timings, _)| timings.queue).collect(),
+    );
+    print_distribution(
+        "Run time",
+        finished.iter().map(|(timings, _)| timings.run).collect(),
+    );
+    lost == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let values: Vec<f64> = (1..=10).map(f64::from).collect();
+        assert_eq!(percentile(&values, 50), 5.0);
+        assert_eq!(percentile(&values, 90), 9.0);
+        assert_eq!(percentile(&values, 99), 10.0);
+        assert_eq!(percentile(&values, 0), 1.0);
+        assert_eq!(percentile(&[2.5], 99), 2.5);
+    }
+}
@@ -0,0 +1,261 @@
+//! Where the server is and how to reach it, from the options and the config file:
+//!
+//! ```yaml
+//! url: https://review.example.com/task_solver
+//! token: secret
+//! timeout: 60
+//! ```
+
+use crate::error::ClientError;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
+    Url,
+};
+use serde::Deserialize;
+use std::{env, fs, io, path::PathBuf, time::Duration};
+use task_solver_protocol::blocking::Client;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+
+/// Connection options, each taken from the command line or else from the config file.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Base URL of the server, used unless an address or a port is given on the command line.
+    pub url: Option<String>,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    /// Sent as `Authorization: Bearer <token>`.
+    pub token: Option<String>,
+    /// Seconds to wait for each response.
+    pub timeout: Option<u64>,
+}
+
+fn invalid(message: String) -> ClientError {
+    ClientError::Config { message }
+}
+
+/// `$XDG_CONFIG_HOME/task_solver_client/config.yaml`, or under `~/.config` if it is not set.
+fn default_path() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => PathBuf::from(directory),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(directory.join("task_solver_client").join("config.yaml"))
+}
+
+/// Checks that the URL is an http or https base URL and drops the trailing slash.
+pub fn parse_url(url: &str) -> Result<String, String> {
+    let parsed = Url::parse(url).map_err(|err| format!("invalid URL \"{url}\": {err}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("URL \"{url}\" is not http or https"));
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!(
+            "URL \"{url}\" has a query or a fragment, expected a base URL"
+        ));
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+impl Config {
+    /// Reads the config file at `path`, or at the default path if there is one there.
+    fn load(path: Option<&str>) -> Result<Config, ClientError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_string(), true),
+            None => match default_path() {
+                Some(path) => (path.to_string_lossy().into_owned(), false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Config::default())
+            }
+            Err(source) => return Err(ClientError::FileRead { path, source }),
+        };
+        // An empty file is not a valid mapping for serde_yaml.
+        if content.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        let config: Config = serde_yaml::from_str(&content)
+            .map_err(|err| invalid(format!("invalid config file {path}: {err}")))?;
+        if let Some(url) = &config.url {
+            parse_url(url)
+                .map_err(|message| invalid(format!("invalid config file {path}: {message}")))?;
+        }
+        if config.timeout == Some(0) {
+            return Err(invalid(format!(
+                "invalid config file {path}: the timeout must be at least 1 second"
+            )));
+        }
+        Ok(config)
+    }
+
+    /// These command line options, with the missing ones taken from the config file.
+    fn or(self, file: Config) -> Config {
+        // An address or a port on the command line means the server is not the configured one.
+        let url = if self.address.is_some() || self.port.is_some() {
+            self.url
+        } else {
+            self.url.or(file.url)
+        };
+        Config {
+            url,
+            address: self.address.or(file.address),
+            port: self.port.or(file.port),
+            token: self.token.or(file.token),
+            timeout: self.timeout.or(file.timeout),
+        }
+    }
+
+    /// Builds the client from these command line options, falling back to the config file at
+    /// `path` and then to `http://127.0.0.1:8080` without a token.
+    pub fn connect(self, path: Option<&str>) -> Result<Client, ClientError> {
+        let Config {
+            url,
+            address,
+            port,
+            token,
+            timeout,
+        } = self.or(Config::load(path)?);
+
+        let mut http = reqwest::blocking::Client::builder();
+        if let Some(token) = token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(|_| invalid("the token is not a valid header value".to_string()))?;
+            value.set_sensitive(true);
+            http = http.default_headers(HeaderMap::from_iter([(AUTHORIZATION, value)]));
+        }
+        if let Some(seconds) = timeout {
+            http = http.timeout(Duration::from_secs(seconds));
+        }
+        let http = http
+            .build()
+            .map_err(|err| invalid(format!("failed to set up the HTTP client: {err}")))?;
+
+        Ok(match url {
+            Some(url) => Client::with_base_url(http, url),
+            None => {
+                let address = address.unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+                Client::with_http_client(http, &address, port.unwrap_or(DEFAULT_PORT))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn url(url: &str) -> Config {
+        Config {
+            url: Some(url.to_string()),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn base_urls() {
+        assert_eq!(
+            parse_url("https://example.com/task_solver/").unwrap(),
+            "https://example.com/task_solver"
+        );
+        assert_eq!(
+            parse_url("http://127.0.0.1:8080").unwrap(),
+            "http://127.0.0.1:8080"
+        );
+        for invalid in [
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/?debug=1",
+            "https://example.com/#top",
+        ] {
+            assert!(parse_url(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn command_line_over_config_file() {
+        let file = || Config {
+            url: Some("https://example.com".to_string()),
+            address: Some("10.0.0.1".to_string()),
+            port: Some(9000),
+            token: Some("file".to_string()),
+            timeout: Some(60),
+        };
+
+        let options = Config::default().or(file());
+        assert_eq!(options.url.as_deref(), Some("https://example.com"));
+        assert_eq!(options.token.as_deref(), Some("file"));
+        assert_eq!(options.timeout, Some(60));
+
+        let options = Config {
+            token: Some("cli".to_string()),
+            timeout: Some(5),
+            ..url("http://localhost:1234")
+        }
+        .or(file());
+        assert_eq!(options.url.as_deref(), Some("http://localhost:1234"));
+        assert_eq!(options.token.as_deref(), Some("cli"));
+        assert_eq!(options.timeout, Some(5));
+
+        // A port on the command line drops the configured URL, but not the configured address.
+        let options = Config {
+            port: Some(8081),
+            ..Config::default()
+        }
+        .or(file());
+        assert_eq!(options.url, None);
+        assert_eq!(options.address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(options.port, Some(8081));
+    }
+
+    #[test]
+    fn connect_falls_back_to_the_defaults() {
+        let path = env::temp_dir().join(format!("task_solver_client_config_{}", process::id()));
+        let path = path.to_str().unwrap();
+        let base_url = |options: Config| {
+            options
+                .connect(Some(path))
+                .map(|client| client.base_url().to_string())
+        };
+
+        fs::write(path, "").unwrap();
+        assert_eq!(
+            base_url(Config::default()).unwrap(),
+            "http://127.0.0.1:8080"
+        );
+        fs::write(
+            path,
+            "url: https://example.com/task_solver/\ntoken: secret\n",
+        )
+        .unwrap();
+        assert_eq!(
+            base_url(Config::default()).unwrap(),
+            "https://example.com/task_solver"
+        );
+        assert_eq!(
+            base_url(url("http://localhost:1234")).unwrap(),
+            "http://localhost:1234"
+        );
+        fs::write(path, "port: 9000\n").unwrap();
+        assert_eq!(
+            base_url(Config::default()).unwrap(),
+            "http://127.0.0.1:9000"
+        );
+        fs::write(path, "timeout: 0\n").unwrap();
+        assert!(matches!(
+            base_url(Config::default()),
+            Err(ClientError::Config { .. })
+        ));
+        fs::remove_file(path).unwrap();
+        assert!(matches!(
+            base_url(Config::default()),
+            Err(ClientError::FileRead { .. })
+        ));
+    }
+}
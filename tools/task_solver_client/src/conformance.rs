@@ -0,0 +1,453 @@
+//! Checks a running server against the contract described in PROJECT2.md.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use task_solver_protocol::{
+    blocking::Client, parse_time, Status, StatusResponse, TaskCountResponse,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const TASK_TIMEOUT: Duration = Duration::from_secs(30);
+const UNKNOWN_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+type CheckResult = Result<(), String>;
+type Check = fn(&Server) -> CheckResult;
+
+struct Server<'a> {
+    client: &'a Client,
+    workers: usize,
+}
+
+impl Server<'_> {
+    /// Sends a raw body and returns the status code and the response body.
+    fn request(&self, post: bool, endpoint: &str, body: String) -> Result<(u16, String), String> {
+        // Raw requests, so that responses the protocol types would reject are reported as such.
+        let url = self.client.url(endpoint);
+        let http = self.client.http();
+        let request = if post {
+            http.post(&url)
+        } else {
+            http.get(&url)
+        };
+        let response = request
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .map_err(|err| format!("request to /{endpoint} failed: {err}"))?;
+        let code = response.status().as_u16();
+        let body = response
+            .text()
+            .map_err(|err| format!("failed to read the response of /{endpoint}: {err}"))?;
+        Ok((code, body))
+    }
+
+    fn request_json<T: DeserializeOwned>(
+        &self,
+        post: bool,
+        endpoint: &str,
+        body: Value,
+    ) -> Result<T, String> {
+        let (code, body) = self.request(post, endpoint, body.to_string())?;
+        if code != 200 {
+            return Err(format!("/{endpoint} answered {code}: {body}"));
+        }
+        serde_json::from_str(&body)
+            .map_err(|err| format!("invalid response of /{endpoint} ({err}): {body}"))
+    }
+
+    fn create(&self, script: &str, args: &str) -> Result<String, String> {
+        let request = json!({"type": "python", "file": script, "args": args});
+        let response: Value = self.request_json(true, "create_task", request)?;
+        match response["id"].as_str() {
+            Some(id) if !id.is_empty() => Ok(id.to_string()),
+            _ => Err(format!("/create_task returned no id: {response}")),
+        }
+    }
+
+    fn status(&self, id: &str) -> Result<StatusResponse, String> {
+        let status: StatusResponse = self.request_json(false, "get_status", json!({ "id": id }))?;
+        check_meta(&status).map_err(|err| format!("task {id}: {err}"))?;
+        Ok(status)
+    }
+
+    fn task_count(&self) -> Result<u32, String> {
+        let response: TaskCountResponse =
+            self.request_json(false, "get_task_count", Value::Null)?;
+        Ok(response.tasks)
+    }
+
+    /// Polls the task until it finishes and returns every observed status.
+    fn wait(&self, id: &str) -> Result<Vec<StatusResponse>, String> {
+        let started = Instant::now();
+        let mut observed = Vec::new();
+        loop {
+            let status = self.status(id)?;
+            let finished = status.status.is_finished();
+            observed.push(status);
+            if finished {
+                return Ok(observed);
+            }
+            if started.elapsed() > TASK_TIMEOUT {
+                return Err(format!("task {id} did not finish in {TASK_TIMEOUT:?}"));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Polls all the tasks until they finish.
+    fn wait_all(&self, ids: &[String]) -> Result<Vec<StatusResponse>, String> {
+        let started = Instant::now();
+        loop {
+            let statuses = ids
+                .iter()
+                .map(|id| self.status(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            if statuses.iter().all(|status| status.status.is_finished()) {
+                return Ok(statuses);
+            }
+            if started.elapsed() > TASK_TIMEOUT {
+                return Err(format!("tasks did not finish in {TASK_TIMEOUT:?}"));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Runs `count` tasks sleeping for `seconds` at once and returns the times each of them
+    /// printed when it started and before it finished.
+    fn run_timed(&self, count: usize, seconds: f64) -> Result<Vec<(f64, f64)>, String> {
+        let script =
+            format!("import time\nprint(time.time())\ntime.sleep({seconds})\nprint(time.time())");
+        let ids = (0..count)
+            .map(|_| self.create(&script, ""))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.wait_all(&ids)?
+            .into_iter()
+            .map(|status| {
+                let stdout = status
+                    .result
+                    .map(|result| result.stdout)
+                    .unwrap_or_default();
+                let times: Vec<f64> = stdout
+                    .split_whitespace()
+                    .filter_map(|time| time.parse().ok())
+                    .collect();
+                match times[..] {
+                    [start, end] if status.status == Status::Success => Ok((start, end)),
+                    _ => Err(format!(
+                        "a sleeping task finished with {} and stdout {stdout:?}",
+                        status.status
+                    )),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Largest number of the intervals that overlap. Intervals that only touch don't overlap.
+fn max_overlap(intervals: &[(f64, f64)]) -> usize {
+    let mut events: Vec<(f64, i32)> = intervals
+        .iter()
+        .flat_map(|&(start, end)| [(start, 1), (end, -1)])
+        .collect();
+    // Ends go before starts at the same time.
+    events.sort_by(|left, right| left.0.total_cmp(&right.0).then(left.1.cmp(&right.1)));
+    let (mut running, mut max_running) = (0, 0);
+    for (_, change) in events {
+        running += change;
+        max_running = max_running.max(running);
+    }
+    max_running as usize
+}
+
+fn rank(status: Status) -> u8 {
+    match status {
+        Status::Wait => 0,
+        Status::Running => 1,
+        Status::Success | Status::Error => 2,
+    }
+}
+
+/// Checks which timestamps and results are present for the status and that the timestamps are ordered.
+fn check_meta(status: &StatusResponse) -> CheckResult {
+    let meta = &status.meta;
+    let (started, finished) = match status.status {
+        Status::Wait => (false, false),
+        Status::Running => (true, false),
+        Status::Success | Status::Error => (true, true),
+    };
+    let presence = |name: &str, value: &Option<String>, expected: bool| match (value, expected) {
+        (Some(_), false) => Err(format!("{} has {name}", status.status)),
+        (None, true) => Err(format!("{} has no {name}", status.status)),
+        _ => Ok(()),
+    };
+    presence("started_at", &meta.started_at, started)?;
+    presence("finished_at", &meta.finished_at, finished)?;
+    if finished != status.result.is_some() {
+        return Err(format!("{} has result {:?}", status.status, status.result));
+    }
+
+    let mut times = vec![parse_time(&meta.created_at)?];
+    for time in [&meta.started_at, &meta.finished_at].into_iter().flatten() {
+        times.push(parse_time(time)?);
+    }
+    if times.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(format!("timestamps are not ordered: {meta:?}"));
+    }
+    Ok(())
+}
+
+fn check_success(server: &Server) -> CheckResult {
+    let id = server.create("import sys\nprint(' '.join(sys.argv[1:]))", "first second")?;
+    let status = server.wait(&id)?.pop().unwrap();
+    let result = status.result.unwrap();
+    if status.status != Status::Success || result.stdout != "first second\n" {
+        return Err(format!(
+            "expected SUCCESS with stdout \"first second\\n\", got {} {result:?}",
+            status.status
+        ));
+    }
+    if result.stderr.is_some() {
+        return Err(format!("SUCCESS has stderr {:?}", result.stderr));
+    }
+    Ok(())
+}
+
+fn check_error(server: &Server) -> CheckResult {
+    let id = server.create("print('partial')\nraise SystemExit('failure')", "")?;
+    let status = server.wait(&id)?.pop().unwrap();
+    let result = status.result.unwrap();
+    if status.status != Status::Error {
+        return Err(format!(
+            "a script exiting with code 1 has status {}",
+            status.status
+        ));
+    }
+    if result.stdout != "partial\n" {
+        return Err(format!(
+            "expected stdout \"partial\\n\", got {:?}",
+            result.stdout
+        ));
+    }
+    match result.stderr {
+        Some(stderr) if stderr.contains("failure") => Ok(()),
+        stderr => Err(format!("expected stderr with \"failure\", got {stderr:?}")),
+    }
+}
+
+fn check_transitions(server: &Server) -> CheckResult {
+    let id = server.create("import time\ntime.sleep(1)", "")?;
+    let observed = server.wait(&id)?;
+    let ranks: Vec<_> = observed.iter().map(|status| rank(status.status)).collect();
+    if ranks.windows(2).any(|pair| pair[0] > pair[1]) {
+        let statuses: Vec<_> = observed
+            .iter()
+            .map(|status| status.status.as_str())
+            .collect();
+        return Err(format!("status went back: {}", statuses.join(" -> ")));
+    }
+    if !ranks.contains(&1) {
+        return Err("a task sleeping for 1s was never seen RUNNING".to_string());
+    }
+    let first = &observed[0].meta;
+    if observed
+        .iter()
+        .any(|status| status.meta.created_at != first.created_at)
+    {
+        return Err("created_at changed while the task was running".to_string());
+    }
+    Ok(())
+}
+
+// The intervals are timed by the scripts themselves, so they lie within the time the tasks
+// held the workers and only count as running at once if they really did.
+fn check_concurrency(server: &Server) -> CheckResult {
+    let running = max_overlap(&server.run_timed(server.workers * 2, 1.5)?);
+    if running > server.workers {
+        return Err(format!(
+            "{running} tasks ran at once with {} workers",
+            server.workers
+        ));
+    }
+    Ok(())
+}
+
+fn check_parallelism(server: &Server) -> CheckResult {
+    let running = max_overlap(&server.run_timed(server.workers, 1.5)?);
+    if running < server.workers {
+        return Err(format!(
+            "at most {running} tasks ran at once with {} workers",
+            server.workers
+        ));
+    }
+    Ok(())
+}
+
+fn check_task_count(server: &Server) -> CheckResult {
+    let queued = 3;
+    let ids = (0..server.workers + queued)
+        .map(|_| server.create("import time\ntime.sleep(2)", ""))
+        .collect::<Result<Vec<_>, _>>()?;
+    let started = Instant::now();
+    loop {
+        let count = server.task_count()?;
+        let statuses = ids
+            .iter()
+            .map(|id| server.status(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let waiting = statuses
+            .iter()
+            .filter(|status| status.status == Status::Wait)
+            .count();
+        let running = statuses
+            .iter()
+            .filter(|status| status.status == Status::Running)
+            .count();
+        // Tasks may start between the requests, so only a steady state is compared.
+        if running == server.workers && waiting == queued && server.task_count()? == count {
+            if count as usize != queued {
+                return Err(format!(
+                    "{queued} tasks wait in the queue, get_task_count returned {count}"
+                ));
+            }
+            break;
+        }
+        if started.elapsed() > TASK_TIMEOUT {
+            return Err(format!(
+                "never saw {} tasks running and {queued} waiting",
+                server.workers
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    server.wait_all(&ids)?;
+    match server.task_count()? {
+        0 => Ok(()),
+        count => Err(format!(
+            "all tasks finished, get_task_count returned {count}"
+        )),
+    }
+}
+
+fn expect_client_error(server: &Server, post: bool, endpoint: &str, body: &str) -> CheckResult {
+    match server.request(post, endpoint, body.to_string())? {
+        (400..=499, _) => Ok(()),
+        (code, response) => Err(format!(
+            "/{endpoint} with {body:?} answered {code}: {response}"
+        )),
+    }
+}
+
+fn check_malformed(server: &Server) -> CheckResult {
+    expect_client_error(server, true, "create_task", "{not json")?;
+    expect_client_error(server, true, "create_task", r#"{"type": "python"}"#)?;
+    expect_client_error(
+        server,
+        true,
+        "create_task",
+        r#"{"type": "ruby", "file": "", "args": ""}"#,
+    )?;
+    expect_client_error(server, false, "get_status", "{not json")
+}
+
+fn check_unknown_id(server: &Server) -> CheckResult {
+    expect_client_error(
+        server,
+        false,
+        "get_status",
+        &json!({ "id": UNKNOWN_ID }).to_string(),
+    )
+}
+
+/// Runs every check against the server started with `workers` workers, prints the report
+/// and returns whether all the checks passed.
+pub fn run(client: &Client, workers: usize) -> bool {
+    let server = Server { client, workers };
+    let checks: [(&str, Check); 8] = [
+        ("successful task returns its stdout", check_success),
+        ("failed task is ERROR with stderr", check_error),
+        ("statuses only move forward", check_transitions),
+        ("running tasks are bounded by workers", check_concurrency),
+        ("every worker runs a task", check_parallelism),
+        ("get_task_count counts waiting tasks", check_task_count),
+        ("malformed requests are rejected", check_malformed),
+        ("unknown id is rejected", check_unknown_id),
+    ];
+    let mut passed = 0;
+    for (name, check) in checks {
+        match check(&server) {
+            Ok(()) => {
+                passed += 1;
+                println!("PASS {name}");
+            }
+            Err(err) => println!("FAIL {name}: {err}"),
+        }
+    }
+    println!("{passed} of {} checks passed", checks.len());
+    passed == checks.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(json: Value) -> StatusResponse {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn meta_of_each_status() {
+        let created_at = "2024-11-10 10:00:00Z";
+        let started_at = "2024-11-10 10:00:01.5Z";
+        let finished_at = "2024-11-10T10:00:02Z";
+        let result = json!({"stdout": "ok\n"});
+        for valid in [
+            json!({"status": "WAIT", "meta": {"created_at": created_at}}),
+            json!({"status": "RUNNING", "meta": {"created_at": created_at, "started_at": started_at}}),
+            json!({"status": "SUCCESS", "meta": {"created_at": created_at, "started_at": started_at, "finished_at": finished_at}, "result": result}),
+        ] {
+            assert_eq!(check_meta(&status(valid.clone())), Ok(()), "{valid}");
+        }
+        for invalid in [
+            json!({"status": "WAIT", "meta": {"created_at": created_at, "started_at": started_at}}),
+            json!({"status": "RUNNING", "meta": {"created_at": created_at}}),
+            json!({"status": "RUNNING", "meta": {"created_at": created_at, "started_at": started_at}, "result": result}),
+            json!({"status": "ERROR", "meta": {"created_at": created_at, "started_at": started_at, "finished_at": finished_at}}),
+            json!({"status": "SUCCESS", "meta": {"created_at": created_at, "started_at": finished_at, "finished_at": started_at}, "result": result}),
+            json!({"status": "WAIT", "meta": {"created_at": "yesterday"}}),
+        ] {
+            assert!(check_meta(&status(invalid.clone())).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn statuses_are_ranked_in_order() {
+        let ranks: Vec<_> = [
+            Status::Wait,
+            Status::Running,
+            Status::Success,
+            Status::Error,
+        ]
+        .into_iter()
+        .map(rank)
+        .collect();
+        assert_eq!(ranks, [0, 1, 2, 2]);
+    }
+
+    #[test]
+    fn overlap_of_intervals() {
+        assert_eq!(max_overlap(&[]), 0);
+        assert_eq!(max_overlap(&[(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)]), 1);
+        assert_eq!(
+            max_overlap(&[(0.0, 1.5), (0.1, 1.6), (1.55, 3.0), (2.0, 2.5)]),
+            2
+        );
+        assert_eq!(
+            max_overlap(&[(0.0, 3.0), (0.5, 1.0), (0.9, 2.0), (2.5, 4.0)]),
+            3
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use std::{error::Error, fmt, io, process::ExitCode};
+
+/// Why a command failed. Each kind exits with its own code, 1 is left for failed tasks
+/// and checks and 2 for invalid arguments.
+#[derive(Debug)]
+pub enum ClientError {
+    Request(task_solver_protocol::Error),
+    FileRead {
+        path: String,
+        source: io::Error,
+    },
+    Manifest {
+        path: String,
+        source: serde_yaml::Error,
+    },
+    /// The task needs a task type or an extension the server does not support.
+    Unsupported {
+        message: String,
+    },
+    /// The dashboard could not use the terminal.
+    Terminal {
+        source: io::Error,
+    },
+    /// The config file or the connection options are invalid.
+    Config {
+        message: String,
+    },
+}
+
+impl ClientError {
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            ClientError::Request(task_solver_protocol::Error::Connection { .. }) => 3,
+            ClientError::Request(task_solver_protocol::Error::Status { .. }) => 4,
+            ClientError::Request(task_solver_protocol::Error::Decode { .. }) => 5,
+            ClientError::FileRead { .. } => 6,
+            ClientError::Manifest { .. } => 7,
+            ClientError::Unsupported { .. } => 8,
+            ClientError::Terminal { .. } => 9,
+            ClientError::Config { .. } => 10,
+        })
+    }
+}
+
+impl From<task_solver_protocol::Error> for ClientError {
+    fn from(err: task_solver_protocol::Error) -> Self {
+        ClientError::Request(err)
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Request(err) => err.fmt(f),
+            ClientError::FileRead { path, source } => write!(f, "failed to read {path}: {source}"),
+            ClientError::Manifest { path, source } => {
+                write!(f, "invalid manifest {path}: {source}")
+            }
+            ClientError::Unsupported { message } => f.write_str(message),
+            ClientError::Terminal { source } => write!(f, "terminal error: {source}"),
+            ClientError::Config { message } => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Request(err) => err.source(),
+            ClientError::FileRead { source, .. } => Some(source),
+            ClientError::Manifest { source, .. } => Some(source),
+            ClientError::Unsupported { .. } => None,
+            ClientError::Terminal { source } => Some(source),
+            ClientError::Config { .. } => None,
+        }
+    }
+}
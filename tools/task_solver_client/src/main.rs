@@ -1,20 +1,54 @@
-use std::fmt;
-use std::fs;
-use std::error::Error;
-use base64::prelude::*;
-use clap::{Parser, Subcommand, ValueEnum};
-use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use clap::{Parser, Subcommand};
+use config::Config;
+use error::ClientError;
+use serde::Serialize;
+use std::process::ExitCode;
+use std::time::Duration;
+use task::{Negotiator, TaskInput};
+use task_solver_protocol::blocking::Client;
+use task_solver_protocol::{
+    CreateTaskResponse, Status, StatusResponse, TaskCountResponse, TaskType,
+};
+
+mod batch;
+mod bench;
+mod config;
+mod conformance;
+mod error;
+mod task;
+mod watch;
 
 #[derive(Parser)]
 #[command(name = "Task Solver client")]
 #[command(about = "Client for interacting with the Task Solver server", long_about = None)]
 struct Cli {
-    #[arg(short, long, default_value = "127.0.0.1")]
-    address: String,
+    /// Address of the server, 127.0.0.1 by default.
+    #[arg(short, long)]
+    address: Option<String>,
+
+    /// Port of the server, 8080 by default.
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// Base URL of the server instead of the address and the port, like `https://example.com/task_solver`.
+    #[arg(long, conflicts_with_all = ["address", "port"], value_parser = config::parse_url)]
+    url: Option<String>,
+
+    /// Sent as a bearer token in the `Authorization` header.
+    #[arg(long)]
+    token: Option<String>,
 
-    #[arg(short, long, default_value = "8080")]
-    port: u16,
+    /// Seconds to wait for each response.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// YAML file with defaults of the options above, `~/.config/task_solver_client/config.yaml` by default.
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Print the responses of the server as JSON.
+    #[arg(long, global = true)]
+    json: bool,
 
     #[command(subcommand)]
     command: Commands,
@@ -23,138 +57,240 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     CreateTask {
+        /// `python`, `bin` or another type the server advertises in `/capabilities`.
         #[arg(short, long)]
         task_type: TaskType,
         #[arg(short, long)]
         file_path: String,
-        #[arg(short, long, default_value = "")]
+        /// Arguments the server splits by whitespace.
+        #[arg(short, long, default_value = "", conflicts_with = "arg")]
         args: String,
+        /// One argument passed as is, can be repeated.
+        #[arg(long, allow_hyphen_values = true)]
+        arg: Vec<String>,
+        /// File written to the standard input of the task.
+        #[arg(long)]
+        stdin: Option<String>,
+        /// Environment variable of the task, can be repeated.
+        #[arg(long, value_name = "KEY=VALUE", value_parser = parse_env)]
+        env: Vec<(String, String)>,
+        /// Wait for the task to finish, print its output and exit with its outcome.
+        #[arg(short, long)]
+        wait: bool,
     },
     GetStatus {
         #[arg(short, long)]
         id: String,
+        /// Poll until the task finishes, print its output and exit with its outcome.
+        #[arg(short, long)]
+        follow: bool,
     },
     GetTaskCount,
+    /// Creates the tasks listed in a YAML manifest and prints their ids as JSON lines.
+    CreateBatch {
+        /// List of entries with `type`, `file` relative to the manifest and optional `args`.
+        #[arg(short, long)]
+        manifest: String,
+        /// Wait for all the tasks to finish and print their statuses instead.
+        #[arg(short, long)]
+        wait: bool,
+    },
+    /// Submits many tasks at once and reports throughput and queue and run time percentiles.
+    Bench {
+        /// Number of tasks to submit.
+        #[arg(short = 'n', long, value_parser = clap::value_parser!(u32).range(1..))]
+        tasks: u32,
+        /// Number of tasks submitted and tracked at the same time.
+        #[arg(short, long, default_value = "4", value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        #[arg(short, long, default_value = "python")]
+        task_type: TaskType,
+        #[arg(short, long = "file")]
+        file_path: String,
+        #[arg(short, long, default_value = "")]
+        args: String,
+    },
+    /// Shows a live dashboard of the tasks and the queue of the server.
+    Watch {
+        /// Id of a task to track, can be repeated.
+        #[arg(short, long)]
+        id: Vec<String>,
+        /// File with the ids to track, one per line or the output of `create-batch`.
+        #[arg(long)]
+        ids: Option<String>,
+        /// Milliseconds between refreshes.
+        #[arg(long, default_value = "1000", value_parser = clap::value_parser!(u64).range(100..))]
+        interval: u64,
+    },
+    /// Checks that a running server follows the protocol and prints a pass/fail report.
+    Conformance {
+        /// Number of workers the server was started with.
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+        workers: u16,
+    },
 }
 
-#[derive(ValueEnum, Clone)]
-enum TaskType {
-    Python,
-    Bin,
-}
-
-impl fmt::Display for TaskType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            TaskType::Bin => write!(f, "bin"),
-            TaskType::Python => write!(f, "python"),
-        }
+fn parse_env(variable: &str) -> Result<(String, String), String> {
+    match variable.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got \"{variable}\"")),
     }
 }
 
-#[derive(Serialize)]
-struct CreateTaskRequest {
-    r#type: String,
-    file: String,
-    args: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct CreateTaskResponse {
-    id: String,
+fn create_task(
+    client: &Client,
+    task_type: &TaskType,
+    file_path: &str,
+    input: &TaskInput,
+) -> Result<String, ClientError> {
+    let request = Negotiator::new(client).build_request(task_type, file_path, input)?;
+    Ok(client.create_task(&request)?)
 }
 
-#[derive(Serialize)]
-struct StatusRequest {
-    id: String,
+fn print_json(value: &impl Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string(value).expect("responses serialize to JSON")
+    );
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct StatusResponse {
-    status: String,
-    meta: Meta,
-    result: Option<ResultData>,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct Meta {
-    created_at: String,
-    started_at: Option<String>,
-    finished_at: Option<String>,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct ResultData {
-    stdout: Option<String>,
-    stderr: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TaskCountResponse {
-    tasks: u32,
-}
-
-fn build_server_url(address: &str, port: u16, endpoint: &str) -> String {
-    format!("http://{}:{}/{}", address, port, endpoint)
+/// Prints the finished task, its output as is unless `json` is set, and returns the exit code for its status.
+fn print_result(status: &StatusResponse, json: bool) -> ExitCode {
+    if json {
+        print_json(status);
+    } else if let Some(result) = &status.result {
+        print!("{}", result.stdout);
+        eprint!("{}", result.stderr.as_deref().unwrap_or_default());
+    }
+    if status.status == Status::Success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
-fn create_task(client: &Client, address: &str, port: u16, task_type: &TaskType, file_path: &str, args: &str) -> Result<String, Box<dyn Error>> {
-    let url = build_server_url(address, port, "create_task");
-    let file_content = match task_type {
-        TaskType::Python => {
-            fs::read_to_string(file_path)
+fn run(cli: &Cli, client: &Client) -> Result<ExitCode, ClientError> {
+    match &cli.command {
+        Commands::CreateTask {
+            task_type,
+            file_path,
+            args,
+            arg,
+            stdin,
+            env,
+            wait,
+        } => {
+            let input = TaskInput {
+                args: args.clone(),
+                argv: arg.clone(),
+                stdin: stdin.clone(),
+                env: env.iter().cloned().collect(),
+            };
+            let task_id = create_task(client, task_type, file_path, &input)?;
+            if *wait {
+                if !cli.json {
+                    // Keep stdout for the output of the task.
+                    eprintln!("New task was created with id: {:?}", task_id);
+                }
+                let status = client.wait(&task_id)?;
+                return Ok(print_result(&status, cli.json));
+            }
+            if cli.json {
+                print_json(&CreateTaskResponse { id: task_id });
+            } else {
+                println!("New task was created with id: {:?}", task_id);
+            }
         }
-        TaskType::Bin => {
-            let binary_content = fs::read(file_path)?;
-            Ok(BASE64_STANDARD.encode(binary_content))
+        Commands::GetStatus { id, follow } => {
+            if *follow {
+                let status = client.wait(id)?;
+                return Ok(print_result(&status, cli.json));
+            }
+            let status = client.get_status(id)?;
+            if cli.json {
+                print_json(&status);
+            } else {
+                println!("Status of the task with id {} is {:?}", id, status);
+            }
         }
-    }?;
-    let request = CreateTaskRequest {
-        r#type: task_type.to_string(),
-        file: file_content.to_string(),
-        args: args.to_string(),
-    };
-    let response = client.post(&url).json(&request).send()?;
-    let response_data: CreateTaskResponse = response.json()?;
-    Ok(response_data.id)
+        Commands::GetTaskCount => {
+            let task_count = client.get_task_count()?;
+            if cli.json {
+                print_json(&TaskCountResponse { tasks: task_count });
+            } else {
+                println!("Number of tasks in the task solver's queue: {}", task_count);
+            }
+        }
+        Commands::CreateBatch { manifest, wait } => {
+            return batch::run(client, manifest, *wait);
+        }
+        Commands::Bench {
+            tasks,
+            concurrency,
+            task_type,
+            file_path,
+            args,
+        } => {
+            let input = TaskInput {
+                args: args.clone(),
+                ..TaskInput::default()
+            };
+            let load = bench::Load {
+                tasks: *tasks as usize,
+                concurrency: (*concurrency).into(),
+                request: Negotiator::new(client).build_request(task_type, file_path, &input)?,
+            };
+            if !bench::run(client, &load) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Watch { id, ids, interval } => {
+            let mut tracked = id.clone();
+            if let Some(ids) = ids {
+                tracked.extend(watch::read_ids(ids)?);
+            }
+            watch::run(client, &tracked, Duration::from_millis(*interval))?;
+        }
+        Commands::Conformance { workers } => {
+            if !conformance::run(client, (*workers).into()) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
 }
 
-fn get_status(client: &Client, address: &str, port: u16, task_id: &str) -> Result<StatusResponse, Box<dyn Error>> {
-    let url = build_server_url(address, port, "get_status");
-    let request = StatusRequest {
-        id: task_id.to_string(),
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let options = Config {
+        url: cli.url.clone(),
+        address: cli.address.clone(),
+        port: cli.port,
+        token: cli.token.clone(),
+        timeout: cli.timeout,
     };
-    let response = client.get(&url).json(&request).send()?;
-    let status_data = response.json()?;
-    Ok(status_data)
+    match options
+        .connect(cli.config.as_deref())
+        .and_then(|client| run(&cli, &client))
+    {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            err.exit_code()
+        }
+    }
 }
 
-fn get_task_count(client: &Client, address: &str, port: u16) -> Result<u32, Box<dyn Error>> {
-    let url = build_server_url(address, port, "get_task_count");
-    let response = client.get(&url).send()?;
-    let count_data: TaskCountResponse = response.json()?;
-    Ok(count_data.tasks)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn main() {
-    let cli = Cli::parse();
-    let client = Client::new();
-
-    match &cli.command {
-        Commands::CreateTask { task_type, file_path, args } => {
-            let task_id = create_task(&client, &cli.address, cli.port, task_type, file_path, args).unwrap();
-            println!("New task was created with id: {:?}", task_id);
-        }
-        Commands::GetStatus { id } => {
-            let status = get_status(&client, &cli.address, cli.port, id).unwrap();
-            println!("Status of the task with id {} is {:?}", id, status);
-        }
-        Commands::GetTaskCount => {
-            let task_count = get_task_count(&client, &cli.address, cli.port).unwrap();
-            println!("Number of tasks in the task solver's queue: {}", task_count);
-        }
+    #[test]
+    fn env_variables() {
+        assert_eq!(parse_env("A=1"), Ok(("A".to_string(), "1".to_string())));
+        assert_eq!(parse_env("A="), Ok(("A".to_string(), String::new())));
+        assert_eq!(parse_env("A=b=c"), Ok(("A".to_string(), "b=c".to_string())));
+        assert!(parse_env("A").is_err());
+        assert!(parse_env("=1").is_err());
     }
 }
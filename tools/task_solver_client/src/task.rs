@@ -0,0 +1,215 @@
+//! Building `/create_task` requests, with the protocol extensions negotiated from `/capabilities`.
+
+use crate::error::ClientError;
+use base64::prelude::*;
+use std::{collections::BTreeMap, fs};
+use task_solver_protocol::{
+    blocking::Client, CapabilitiesResponse, CreateTaskRequest, Encoding, TaskType, FEATURE_ARGV,
+    FEATURE_ENV, FEATURE_STDIN,
+};
+
+/// Arguments, input and environment of a task, on top of its file.
+#[derive(Default)]
+pub struct TaskInput {
+    /// Arguments the server splits by whitespace.
+    pub args: String,
+    /// Arguments passed as is after `args`, sent in `argv` if any of them can't go in `args`.
+    pub argv: Vec<String>,
+    /// Path of the file written to the standard input of the task.
+    pub stdin: Option<String>,
+    pub env: BTreeMap<String, String>,
+}
+
+fn read_file(file_path: &str, encoding: Encoding) -> Result<String, ClientError> {
+    let file_content = match encoding {
+        Encoding::Text => fs::read_to_string(file_path),
+        Encoding::Base64 => {
+            fs::read(file_path).map(|binary_content| BASE64_STANDARD.encode(binary_content))
+        }
+    };
+    file_content.map_err(|source| ClientError::FileRead {
+        path: file_path.to_string(),
+        source,
+    })
+}
+
+fn unsupported(message: String) -> ClientError {
+    ClientError::Unsupported { message }
+}
+
+/// Builds the requests of a server, fetching its capabilities only once an extension is needed.
+pub struct Negotiator<'a> {
+    client: &'a Client,
+    /// `None` until fetched, then `None` inside if the server has no `/capabilities`.
+    capabilities: Option<Option<CapabilitiesResponse>>,
+}
+
+impl<'a> Negotiator<'a> {
+    pub fn new(client: &'a Client) -> Self {
+        Negotiator {
+            client,
+            capabilities: None,
+        }
+    }
+
+    fn capabilities(&mut self) -> Result<Option<&CapabilitiesResponse>, ClientError> {
+        if self.capabilities.is_none() {
+            self.capabilities = Some(self.client.capabilities()?);
+        }
+        Ok(self.capabilities.as_ref().and_then(Option::as_ref))
+    }
+
+    fn encoding(&mut self, task_type: &TaskType) -> Result<Encoding, ClientError> {
+        // Only these two are part of the protocol, other types have to be advertised.
+        if let TaskType::Python | TaskType::Bin = task_type {
+            return Ok(task_type
+                .encoding()
+                .expect("protocol task types have an encoding"));
+        }
+        match self.capabilities()? {
+            Some(capabilities) => capabilities.types.get(task_type).copied().ok_or_else(|| {
+                let types: Vec<_> = capabilities.types.keys().map(TaskType::as_str).collect();
+                unsupported(format!("the server does not support task type \"{task_type}\", it supports {}", types.join(", ")))
+            }),
+            None => Err(unsupported(format!("the server has no /capabilities, task type \"{task_type}\" is not part of the protocol"))),
+        }
+    }
+
+    fn require(&mut self, feature: &str, reason: &str) -> Result<(), ClientError> {
+        match self.capabilities()? {
+            Some(capabilities) if capabilities.supports(feature) => Ok(()),
+            Some(_) => Err(unsupported(format!(
+                "{reason} needs the \"{feature}\" extension the server does not support"
+            ))),
+            None => Err(unsupported(format!(
+                "{reason} needs the \"{feature}\" extension, the server has no /capabilities"
+            ))),
+        }
+    }
+
+    pub fn build_request(
+        &mut self,
+        task_type: &TaskType,
+        file_path: &str,
+        input: &TaskInput,
+    ) -> Result<CreateTaskRequest, ClientError> {
+        let file = read_file(file_path, self.encoding(task_type)?)?;
+        let mut request = CreateTaskRequest {
+            args: input.args.clone(),
+            ..CreateTaskRequest::new(task_type.clone(), file)
+        };
+        if !input.argv.is_empty() {
+            // `args` is split by whitespace anyway, so both go in one list.
+            let argv: Vec<String> = input
+                .args
+                .split_whitespace()
+                .map(String::from)
+                .chain(input.argv.iter().cloned())
+                .collect();
+            // Arguments the server splits back the same way don't need the extension.
+            if argv
+                .iter()
+                .all(|arg| !arg.is_empty() && !arg.contains(char::is_whitespace))
+            {
+                request.args = argv.join(" ");
+            } else {
+                self.require(FEATURE_ARGV, "an empty argument or one with whitespace")?;
+                request.args = String::new();
+                request.argv = Some(argv);
+            }
+        }
+        if let Some(stdin) = &input.stdin {
+            self.require(FEATURE_STDIN, "standard input")?;
+            request.stdin = Some(read_file(stdin, Encoding::Text)?);
+        }
+        if !input.env.is_empty() {
+            self.require(FEATURE_ENV, "environment variables")?;
+            request.env = input.env.clone();
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    const FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+    /// Client of a server answering every request with `code` and `body`.
+    fn serve(code: u16, body: &'static str) -> Client {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let _ = stream.read(&mut [0; 4096]);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {code} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        Client::new(&address.ip().to_string(), address.port())
+    }
+
+    fn input(args: &str, argv: &[&str]) -> TaskInput {
+        TaskInput {
+            args: args.to_string(),
+            argv: argv.iter().map(|arg| arg.to_string()).collect(),
+            ..TaskInput::default()
+        }
+    }
+
+    #[test]
+    fn plain_arguments_fold_into_args() {
+        // Nothing listens there, so the request is built without `/capabilities`.
+        let client = Client::new("127.0.0.1", 9);
+        let mut negotiator = Negotiator::new(&client);
+
+        let request = negotiator
+            .build_request(&TaskType::Python, FILE, &input(" a  b ", &[]))
+            .unwrap();
+        assert_eq!((request.args.as_str(), request.argv), (" a  b ", None));
+        let request = negotiator
+            .build_request(&TaskType::Python, FILE, &input(" a  b ", &["-c", "d"]))
+            .unwrap();
+        assert_eq!((request.args.as_str(), request.argv), ("a b -c d", None));
+        assert_eq!(request.file, fs::read_to_string(FILE).unwrap());
+        let request = negotiator
+            .build_request(&TaskType::Bin, FILE, &input("", &[]))
+            .unwrap();
+        assert_eq!(
+            request.file,
+            BASE64_STANDARD.encode(fs::read(FILE).unwrap())
+        );
+    }
+
+    #[test]
+    fn argv_needs_the_extension() {
+        let argv = input("a", &["with space", ""]);
+        let client = serve(
+            200,
+            r#"{"types": {"python": "text", "bin": "base64"}, "features": ["argv"]}"#,
+        );
+        let request = Negotiator::new(&client)
+            .build_request(&TaskType::Python, FILE, &argv)
+            .unwrap();
+        assert_eq!(request.args, "");
+        assert_eq!(request.argv.unwrap(), ["a", "with space", ""]);
+
+        for client in [
+            serve(200, r#"{"types": {"python": "text"}, "features": []}"#),
+            serve(404, "not found"),
+        ] {
+            let result = Negotiator::new(&client).build_request(&TaskType::Python, FILE, &argv);
+            assert!(matches!(result, Err(ClientError::Unsupported { .. })));
+        }
+    }
+}
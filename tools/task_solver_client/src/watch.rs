@@ -0,0 +1,299 @@
+//! Live terminal dashboard of the tracked tasks and the queue of the server.
+
+use crate::error::ClientError;
+use chrono::{DateTime, Local, Utc};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use serde_json::Value;
+use std::{
+    fs, io,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+use task_solver_protocol::{blocking::Client, parse_time, Status, StatusResponse};
+
+/// How often the durations of the running tasks are redrawn, independent of the refreshes.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+const STATUSES: [Status; 4] = [
+    Status::Wait,
+    Status::Running,
+    Status::Success,
+    Status::Error,
+];
+
+/// Reads the ids to track from a file with one per line, either plain or as the JSON lines
+/// printed by `create-batch`.
+pub fn read_ids(file_path: &str) -> Result<Vec<String>, ClientError> {
+    let content = fs::read_to_string(file_path).map_err(|source| ClientError::FileRead {
+        path: file_path.to_string(),
+        source,
+    })?;
+    let ids = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(task)) => task
+                .get("id")
+                .and_then(Value::as_str)
+                .unwrap_or(line)
+                .to_string(),
+            _ => line.to_string(),
+        });
+    Ok(ids.collect())
+}
+
+/// What the last refresh got from the server.
+struct Snapshot {
+    queue: Result<u32, ClientError>,
+    tasks: Vec<(String, Result<StatusResponse, ClientError>)>,
+    refreshed_at: DateTime<Local>,
+}
+
+fn refresh(client: &Client, ids: &[String]) -> Snapshot {
+    Snapshot {
+        queue: client.get_task_count().map_err(ClientError::from),
+        tasks: ids
+            .iter()
+            .map(|id| (id.clone(), client.get_status(id).map_err(ClientError::from)))
+            .collect(),
+        refreshed_at: Local::now(),
+    }
+}
+
+/// Sends a snapshot every `interval` until the dashboard is closed.
+fn poll(client: Client, ids: Vec<String>, interval: Duration, snapshots: Sender<Snapshot>) {
+    loop {
+        let started = Instant::now();
+        if snapshots.send(refresh(&client, &ids)).is_err() {
+            return;
+        }
+        thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+struct Watch<'a> {
+    client: &'a Client,
+}
+
+impl Watch<'_> {
+    fn title(&self) -> String {
+        format!(" Task solver at {} ", self.client.base_url())
+    }
+
+    fn draw(&self, frame: &mut Frame, snapshot: Option<&Snapshot>) {
+        let [header, table, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        match snapshot {
+            Some(snapshot) => {
+                frame.render_widget(self.header(snapshot), header);
+                frame.render_widget(tasks_table(snapshot), table);
+            }
+            None => frame.render_widget(
+                Paragraph::new("Waiting for the server...")
+                    .block(Block::bordered().title(self.title())),
+                header,
+            ),
+        }
+        frame.render_widget(
+            Line::from("q: quit").style(Style::new().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn header(&self, snapshot: &Snapshot) -> Paragraph<'static> {
+        let queue = match &snapshot.queue {
+            Ok(count) => Span::raw(format!("{count} tasks in the queue")),
+            Err(err) => Span::styled(
+                format!("get_task_count failed: {err}"),
+                Style::new().fg(Color::Red),
+            ),
+        };
+        let mut counts = vec![Span::raw(format!("{} tracked: ", snapshot.tasks.len()))];
+        for status in STATUSES {
+            let count = snapshot
+                .tasks
+                .iter()
+                .filter(|(_, task)| matches!(task, Ok(task) if task.status == status))
+                .count();
+            counts.push(Span::styled(
+                format!("{status} {count}  "),
+                status_style(status),
+            ));
+        }
+        let lines = vec![
+            Line::from(vec![
+                queue,
+                Span::raw(format!(
+                    ", refreshed at {}",
+                    snapshot.refreshed_at.format("%H:%M:%S")
+                )),
+            ]),
+            Line::from(counts),
+        ];
+        Paragraph::new(lines).block(Block::bordered().title(self.title()))
+    }
+}
+
+fn status_style(status: Status) -> Style {
+    match status {
+        Status::Wait => Style::new().fg(Color::Yellow),
+        Status::Running => Style::new().fg(Color::Cyan),
+        Status::Success => Style::new().fg(Color::Green),
+        Status::Error => Style::new().fg(Color::Red),
+    }
+}
+
+/// Seconds between the timestamps, up to now if the second one is not there yet.
+fn duration(from: Option<&str>, to: Option<&str>) -> String {
+    let Some(Ok(from)) = from.map(parse_time) else {
+        return String::new();
+    };
+    let to = match to.map(parse_time) {
+        Some(Ok(to)) => to,
+        Some(Err(_)) => return String::new(),
+        None => Utc::now(),
+    };
+    format!("{}s", (to - from).num_seconds())
+}
+
+fn tasks_table(snapshot: &Snapshot) -> Table<'static> {
+    let rows = snapshot.tasks.iter().map(|(id, task)| match task {
+        Ok(task) => {
+            let meta = &task.meta;
+            Row::new(vec![
+                Cell::from(id.clone()),
+                Cell::from(task.status.as_str()).style(status_style(task.status)),
+                Cell::from(meta.created_at.clone()),
+                Cell::from(duration(Some(&meta.created_at), meta.started_at.as_deref())),
+                Cell::from(duration(
+                    meta.started_at.as_deref(),
+                    meta.finished_at.as_deref(),
+                )),
+            ])
+        }
+        // The message goes in the widest column.
+        Err(err) => Row::new(vec![
+            Cell::from(id.clone()),
+            Cell::from("?"),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(err.to_string()),
+        ])
+        .style(Style::new().fg(Color::Red)),
+    });
+    let widths = [
+        Constraint::Length(36),
+        Constraint::Length(8),
+        Constraint::Length(20),
+        Constraint::Length(8),
+        Constraint::Fill(1),
+    ];
+    Table::new(rows, widths)
+        .header(
+            Row::new(["ID", "STATUS", "CREATED", "QUEUED", "RUNNING"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(" Tasks "))
+}
+
+/// Returns once `q`, `Esc` or `Ctrl-C` is pressed, or the timeout passes.
+fn quit_pressed(timeout: Duration) -> io::Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(false);
+        }
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if key.kind == KeyEventKind::Press
+                && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c)
+            {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+fn watch_loop(
+    terminal: &mut DefaultTerminal,
+    watch: &Watch,
+    snapshots: &Receiver<Snapshot>,
+) -> io::Result<()> {
+    let mut latest = None;
+    loop {
+        // A slow server must not delay the keys, so the snapshots come from another thread.
+        latest = snapshots.try_iter().last().or(latest);
+        terminal.draw(|frame| watch.draw(frame, latest.as_ref()))?;
+        if quit_pressed(REDRAW_INTERVAL)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Shows the dashboard, refreshing it every `interval` until the user quits.
+pub fn run(client: &Client, ids: &[String], interval: Duration) -> Result<(), ClientError> {
+    let (sender, snapshots) = mpsc::channel();
+    let (poller_client, poller_ids) = (client.clone(), ids.to_vec());
+    // Not joined, a request in flight would keep the dashboard open after the user quits.
+    thread::spawn(move || poll(poller_client, poller_ids, interval, sender));
+
+    let watch = Watch { client };
+    let mut terminal = ratatui::try_init().map_err(|source| ClientError::Terminal { source })?;
+    let result = watch_loop(&mut terminal, &watch, &snapshots);
+    ratatui::restore();
+    result.map_err(|source| ClientError::Terminal { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn ids_from_plain_and_json_lines() {
+        let path = env::temp_dir().join(format!("task_solver_client_ids_{}", process::id()));
+        let lines = [
+            r#"{"id": "1f0c"}"#,
+            "",
+            "  2b7e  ",
+            r#"{"error": "bad file"}"#,
+            r#""3a9d""#,
+        ];
+        fs::write(&path, lines.join("\n")).unwrap();
+        let ids = read_ids(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            ids.unwrap(),
+            ["1f0c", "2b7e", r#"{"error": "bad file"}"#, r#""3a9d""#]
+        );
+        assert!(matches!(
+            read_ids(path.to_str().unwrap()),
+            Err(ClientError::FileRead { .. })
+        ));
+    }
+
+    #[test]
+    fn durations_between_timestamps() {
+        let created = "2024-11-10 10:00:00Z";
+        assert_eq!(duration(Some(created), Some("2024-11-10T10:01:05Z")), "65s");
+        assert_eq!(duration(None, Some(created)), "");
+        assert_eq!(duration(Some("soon"), None), "");
+        assert_eq!(duration(Some(created), Some("later")), "");
+        let running = duration(Some(created), None);
+        assert!(running.trim_end_matches('s').parse::<i64>().unwrap() > 0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Client sending the requests with `reqwest::blocking`.
+
+use crate::{
+    base_url,
+    error::{decode, optional_capabilities, Error},
+    BaseUrl, CapabilitiesResponse, CreateTaskRequest, CreateTaskResponse, PollDelays,
+    StatusRequest, StatusResponse, TaskCountResponse, MAX_POLL_DELAY,
+};
+use reqwest::blocking::{RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use std::{thread, time::Duration};
+
+/// Blocking client of a Task solver server.
+///
+/// ```no_run
+/// use task_solver_protocol::blocking::Client;
+///
+/// let client = Client::new("127.0.0.1", 8080);
+/// println!("{} tasks in the queue", client.get_task_count()?);
+/// # Ok::<(), task_solver_protocol::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::blocking::Client,
+    base_url: BaseUrl,
+}
+
+impl Client {
+    pub fn new(address: &str, port: u16) -> Self {
+        Self::with_http_client(reqwest::blocking::Client::new(), address, port)
+    }
+
+    /// Client sending the requests with `http`, configured with timeouts or proxies.
+    pub fn with_http_client(http: reqwest::blocking::Client, address: &str, port: u16) -> Self {
+        Self::with_base_url(http, base_url(address, port))
+    }
+
+    /// Client of a server under `base_url`, like `https://example.com/task_solver` behind a
+    /// reverse proxy.
+    pub fn with_base_url(http: reqwest::blocking::Client, base_url: impl Into<String>) -> Self {
+        Client {
+            http,
+            base_url: BaseUrl::new(base_url),
+        }
+    }
+
+    pub fn http(&self) -> &reqwest::blocking::Client {
+        &self.http
+    }
+
+    /// URL the endpoints are under, like `http://127.0.0.1:8080`.
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    /// URL of the endpoint, like `create_task`.
+    pub fn url(&self, endpoint: &str) -> String {
+        self.base_url.endpoint(endpoint)
+    }
+
+    fn send<T: DeserializeOwned>(&self, url: &str, request: RequestBuilder) -> Result<T, Error> {
+        let response: Response = request.send().map_err(Error::connection(url))?;
+        let code = response.status();
+        let body = response.text().map_err(Error::connection(url))?;
+        decode(url, code, body)
+    }
+
+    /// Queues a task and returns its id.
+    pub fn create_task(&self, request: &CreateTaskRequest) -> Result<String, Error> {
+        let url = self.url("create_task");
+        let response: CreateTaskResponse = self.send(&url, self.http.post(&url).json(request))?;
+        Ok(response.id)
+    }
+
+    pub fn get_status(&self, id: &str) -> Result<StatusResponse, Error> {
+        let url = self.url("get_status");
+        let request = StatusRequest { id: id.to_string() };
+        self.send(&url, self.http.get(&url).json(&request))
+    }
+
+    /// Number of tasks waiting in the queue.
+    pub fn get_task_count(&self) -> Result<u32, Error> {
+        let url = self.url("get_task_count");
+        let response: TaskCountResponse = self.send(&url, self.http.get(&url))?;
+        Ok(response.tasks)
+    }
+
+    /// Extensions the server supports, `None` if it implements only the base protocol and
+    /// answers `/capabilities` with 404 or 405.
+    pub fn capabilities(&self) -> Result<Option<CapabilitiesResponse>, Error> {
+        let url = self.url("capabilities");
+        optional_capabilities(self.send(&url, self.http.get(&url)))
+    }
+
+    /// Polls the task until it finishes, with delays growing up to [`MAX_POLL_DELAY`].
+    pub fn wait(&self, id: &str) -> Result<StatusResponse, Error> {
+        self.wait_with_max_delay(id, MAX_POLL_DELAY)
+    }
+
+    /// Polls the task until it finishes, with delays growing up to `max_delay`.
+    pub fn wait_with_max_delay(
+        &self,
+        id: &str,
+        max_delay: Duration,
+    ) -> Result<StatusResponse, Error> {
+        let mut delays = PollDelays::new(max_delay);
+        loop {
+            let status = self.get_status(id)?;
+            if status.status.is_finished() {
+                return Ok(status);
+            }
+            thread::sleep(delays.next_delay());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stub, Status, TaskType};
+
+    #[test]
+    fn requests() {
+        let address = stub::serve(stub::task_server);
+        let client = Client::new(&address.ip().to_string(), address.port());
+        let id = client
+            .create_task(&CreateTaskRequest::new(TaskType::Python, "print('hi')"))
+            .unwrap();
+        let status = client
+            .wait_with_max_delay(&id, Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(status.status, Status::Success);
+        assert_eq!(status.result.unwrap().stdout, "hi\n");
+        assert_eq!(client.get_task_count().unwrap(), 3);
+        assert!(client.capabilities().unwrap().is_none());
+        assert!(matches!(
+            client.get_status("2"),
+            Err(Error::Status { code: 404, .. })
+        ));
+
+        let prefixed =
+            Client::with_base_url(client.http().clone(), format!("http://{address}/api/"));
+        assert_eq!(
+            prefixed.url("get_status"),
+            format!("http://{address}/api/get_status")
+        );
+    }
+
+    #[test]
+    fn capabilities_only_missing_without_extensions() {
+        for (code, missing) in [(404, true), (405, true), (400, false), (500, false)] {
+            let address = stub::serve(match code {
+                404 => |_, _, _| (404, "not found".to_string()),
+                405 => |_, _, _| (405, "method not allowed".to_string()),
+                400 => |_, _, _| (400, "bad request".to_string()),
+                _ => |_, _, _| (500, "internal error".to_string()),
+            });
+            let client = Client::new(&address.ip().to_string(), address.port());
+            match client.capabilities() {
+                Ok(None) => assert!(missing, "{code}"),
+                Err(Error::Status { code: status, .. }) => {
+                    assert!(!missing && status == code, "{code}")
+                }
+                other => panic!("{code}: {other:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::CapabilitiesResponse;
+use serde::de::DeserializeOwned;
+use std::{error, fmt};
+
+/// Longest part of an unexpected response body shown in the messages.
+const MAX_BODY_LENGTH: usize = 500;
+
+/// Why a request failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The request was not sent or the response was not received.
+    Connection { url: String, source: reqwest::Error },
+    /// The server answered with a non-2xx status.
+    Status {
+        url: String,
+        code: u16,
+        body: String,
+    },
+    /// The response is not the JSON the protocol describes.
+    Decode {
+        url: String,
+        body: String,
+        source: serde_json::Error,
+    },
+}
+
+impl Error {
+    pub(crate) fn connection(url: &str) -> impl FnOnce(reqwest::Error) -> Error + '_ {
+        move |source| Error::Connection {
+            url: url.to_string(),
+            source,
+        }
+    }
+}
+
+/// Checks the status of the response and decodes its JSON body.
+pub(crate) fn decode<T: DeserializeOwned>(
+    url: &str,
+    code: reqwest::StatusCode,
+    body: String,
+) -> Result<T, Error> {
+    if !code.is_success() {
+        return Err(Error::Status {
+            url: url.to_string(),
+            code: code.as_u16(),
+            body,
+        });
+    }
+    serde_json::from_str(&body).map_err(|source| Error::Decode {
+        url: url.to_string(),
+        body,
+        source,
+    })
+}
+
+/// Answer to `/capabilities`, `None` if the server has no such endpoint because it implements
+/// only the base protocol. Other failures are errors, so that a server with extensions is not
+/// mistaken for one without them.
+pub(crate) fn optional_capabilities(
+    response: Result<CapabilitiesResponse, Error>,
+) -> Result<Option<CapabilitiesResponse>, Error> {
+    match response {
+        Ok(capabilities) => Ok(Some(capabilities)),
+        Err(Error::Status {
+            code: 404 | 405, ..
+        }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn shorten(body: &str) -> &str {
+    let body = body.trim();
+    match body.char_indices().nth(MAX_BODY_LENGTH) {
+        Some((end, _)) => &body[..end],
+        None => body,
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connection { url, source } if source.is_connect() => {
+                write!(f, "could not connect to {url}, is the server running?")
+            }
+            Error::Connection { url, source } if source.is_timeout() => {
+                write!(f, "request to {url} timed out")
+            }
+            Error::Connection { url, source } => write!(f, "request to {url} failed: {source}"),
+            Error::Status { url, code, body } => {
+                write!(
+                    f,
+                    "server answered {url} with status {code}: {}",
+                    shorten(body)
+                )
+            }
+            Error::Decode { url, body, source } => {
+                write!(
+                    f,
+                    "unexpected response from {url} ({source}): {}",
+                    shorten(body)
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Connection { source, .. } => Some(source),
+            Error::Status { .. } => None,
+            Error::Decode { source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Wire format of the Task solver API from `PROJECT2.md` and clients for it.
+//!
+//! The request and response types are shared by the reference server, `task_solver_client`
+//! and integration tests of servers. With the `blocking` feature [`blocking::Client`] sends
+//! them with `reqwest::blocking`, with the `async` feature [`Client`] does the same in async
+//! code. Both features are enabled by default.
+//!
+//! ```no_run
+//! use task_solver_protocol::{blocking::Client, CreateTaskRequest, Status, TaskType};
+//!
+//! let client = Client::new("127.0.0.1", 8080);
+//! let id = client.create_task(&CreateTaskRequest::new(TaskType::Python, "print('hi')"))?;
+//! let status = client.wait(&id)?;
+//! assert_eq!(status.status, Status::Success);
+//! assert_eq!(status.result.unwrap().stdout, "hi\n");
+//! # Ok::<(), task_solver_protocol::Error>(())
+//! ```
+
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(any(feature = "async", feature = "blocking"))]
+mod error;
+mod types;
+
+#[cfg(feature = "async")]
+pub use asynchronous::Client;
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub use error::Error;
+pub use types::*;
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::time::Duration;
+
+/// First delay between the polls of [`Client::wait`] and [`blocking::Client::wait`], doubled
+/// after each poll up to [`MAX_POLL_DELAY`].
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub const FIRST_POLL_DELAY: Duration = Duration::from_millis(100);
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub const MAX_POLL_DELAY: Duration = Duration::from_secs(2);
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn base_url(address: &str, port: u16) -> String {
+    format!("http://{address}:{port}")
+}
+
+/// URL the endpoints of a server are under, without the trailing slash.
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Clone, Debug)]
+struct BaseUrl(String);
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl BaseUrl {
+    fn new(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        base_url.truncate(base_url.trim_end_matches('/').len());
+        BaseUrl(base_url)
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn endpoint(&self, endpoint: &str) -> String {
+        format!("{}/{endpoint}", self.0)
+    }
+}
+
+/// Delays between the polls of `wait`, doubling from [`FIRST_POLL_DELAY`] up to the maximum.
+#[cfg(any(feature = "async", feature = "blocking"))]
+struct PollDelays {
+    next: Duration,
+    max: Duration,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl PollDelays {
+    fn new(max: Duration) -> Self {
+        PollDelays {
+            next: FIRST_POLL_DELAY.min(max),
+            max,
+        }
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (delay * 2).min(self.max);
+        delay
+    }
+}
+
+#[cfg(all(test, any(feature = "async", feature = "blocking")))]
+mod stub {
+    use std::{net::SocketAddr, thread};
+    use tiny_http::{Response, Server};
+
+    /// Starts a server answering every request with the response of `respond` to its method,
+    /// path and body.
+    pub fn serve(respond: fn(&str, &str, &str) -> (u16, String)) -> SocketAddr {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let (code, response) = respond(request.method().as_str(), request.url(), &body);
+                let _ = request.respond(Response::from_string(response).with_status_code(code));
+            }
+        });
+        address
+    }
+
+    /// Answers like a server with one task that finishes on the second `/get_status`.
+    pub fn task_server(method: &str, path: &str, body: &str) -> (u16, String) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static POLLS: AtomicUsize = AtomicUsize::new(0);
+        match (method, path) {
+            ("POST", "/create_task") if body.contains(r#""type":"python""#) => {
+                (200, r#"{"id": "1"}"#.to_string())
+            }
+            ("GET", "/get_status") if body == r#"{"id":"1"}"# => {
+                let status = if POLLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                    r#"{"status": "RUNNING", "meta": {"created_at": "2024-11-10 00:00:00Z", "started_at": "2024-11-10 00:00:01Z"}}"#
+                } else {
+                    r#"{"status": "SUCCESS", "meta": {"created_at": "2024-11-10 00:00:00Z", "started_at": "2024-11-10 00:00:01Z", "finished_at": "2024-11-10 00:00:02Z"}, "result": {"stdout": "hi\n"}}"#
+                };
+                (200, status.to_string())
+            }
+            ("GET", "/get_task_count") => (200, r#"{"tasks": 3}"#.to_string()),
+            _ => (404, "not found".to_string()),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "async", feature = "blocking")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_urls_and_poll_delays() {
+        let url = BaseUrl::new("https://example.com/task_solver//");
+        assert_eq!(url.as_str(), "https://example.com/task_solver");
+        assert_eq!(
+            url.endpoint("get_status"),
+            "https://example.com/task_solver/get_status"
+        );
+
+        let mut delays = PollDelays::new(Duration::from_millis(500));
+        let delays: Vec<_> = (0..5).map(|_| delays.next_delay().as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 500, 500]);
+        assert_eq!(
+            PollDelays::new(Duration::from_millis(10)).next_delay(),
+            Duration::from_millis(10)
+        );
+    }
+}
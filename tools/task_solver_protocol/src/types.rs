@@ -0,0 +1,302 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::Infallible, fmt, str::FromStr};
+
+/// Format of the `meta` timestamps, like `2024-11-10 00:00:00Z`.
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%SZ";
+
+/// Extension of the protocol: `argv` of [`CreateTaskRequest`].
+pub const FEATURE_ARGV: &str = "argv";
+/// Extension of the protocol: `stdin` of [`CreateTaskRequest`].
+pub const FEATURE_STDIN: &str = "stdin";
+/// Extension of the protocol: `env` of [`CreateTaskRequest`].
+pub const FEATURE_ENV: &str = "env";
+
+/// Parses a `meta` timestamp, in [`TIME_FORMAT`] with optional fractions of a second or in RFC 3339.
+///
+/// ```
+/// let time = task_solver_protocol::parse_time("2024-11-10 00:00:00Z").unwrap();
+/// assert_eq!(time.to_rfc3339(), "2024-11-10T00:00:00+00:00");
+/// assert!(task_solver_protocol::parse_time("yesterday").is_err());
+/// ```
+pub fn parse_time(time: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.fZ")
+        .map(|time| time.and_utc())
+        .map_err(|_| format!("timestamp \"{time}\" is not like \"2024-11-10 00:00:00Z\""))
+}
+
+/// Type of a task. `python` and `bin` are part of the protocol, the others are extensions a
+/// server advertises in [`CapabilitiesResponse`].
+///
+/// ```
+/// use task_solver_protocol::{Encoding, TaskType};
+///
+/// let task_type: TaskType = "wasm".parse().unwrap();
+/// assert_eq!(task_type, TaskType::Other("wasm".to_string()));
+/// assert_eq!(task_type.encoding(), None);
+/// assert_eq!(TaskType::Bin.encoding(), Some(Encoding::Base64));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum TaskType {
+    /// Python script sent as plain text.
+    Python,
+    /// Executable sent in base64.
+    Bin,
+    /// `sh` script sent as plain text.
+    Shell,
+    Other(String),
+}
+
+impl TaskType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskType::Python => "python",
+            TaskType::Bin => "bin",
+            TaskType::Shell => "shell",
+            TaskType::Other(name) => name,
+        }
+    }
+
+    /// How the `file` of the type is sent, `None` for the types this crate doesn't know.
+    pub fn encoding(&self) -> Option<Encoding> {
+        match self {
+            TaskType::Python | TaskType::Shell => Some(Encoding::Text),
+            TaskType::Bin => Some(Encoding::Base64),
+            TaskType::Other(_) => None,
+        }
+    }
+}
+
+impl From<String> for TaskType {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "python" => TaskType::Python,
+            "bin" => TaskType::Bin,
+            "shell" => TaskType::Shell,
+            _ => TaskType::Other(name),
+        }
+    }
+}
+
+impl From<TaskType> for String {
+    fn from(task_type: TaskType) -> Self {
+        task_type.as_str().to_string()
+    }
+}
+
+impl FromStr for TaskType {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(TaskType::from(name.to_string()))
+    }
+}
+
+impl fmt::Display for TaskType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How the `file` of a task type is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Text,
+    Base64,
+}
+
+/// Body of `POST /create_task`.
+///
+/// ```
+/// use task_solver_protocol::{CreateTaskRequest, TaskType};
+///
+/// let request = CreateTaskRequest { args: "1 2".to_string(), ..CreateTaskRequest::new(TaskType::Python, "print(1)") };
+/// assert_eq!(
+///     serde_json::to_string(&request).unwrap(),
+///     r#"{"type":"python","file":"print(1)","args":"1 2"}"#
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CreateTaskRequest {
+    pub r#type: TaskType,
+    /// Text of the task, or its base64 if the type is binary.
+    pub file: String,
+    /// Arguments the server splits by whitespace.
+    #[serde(default)]
+    pub args: String,
+    /// Arguments passed as is, instead of `args`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argv: Option<Vec<String>>,
+    /// Text written to the standard input of the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl CreateTaskRequest {
+    /// Request of a task without arguments, input and environment.
+    pub fn new(task_type: TaskType, file: impl Into<String>) -> Self {
+        CreateTaskRequest {
+            r#type: task_type,
+            file: file.into(),
+            args: String::new(),
+            argv: None,
+            stdin: None,
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CreateTaskResponse {
+    pub id: String,
+}
+
+/// Body of `GET /get_status`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatusRequest {
+    pub id: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Status {
+    Wait,
+    Running,
+    Success,
+    Error,
+}
+
+impl Status {
+    /// Whether the task has finished, with `SUCCESS` or `ERROR`.
+    pub fn is_finished(self) -> bool {
+        matches!(self, Status::Success | Status::Error)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Wait => "WAIT",
+            Status::Running => "RUNNING",
+            Status::Success => "SUCCESS",
+            Status::Error => "ERROR",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Timestamps of a task: `started_at` is present once it is `RUNNING`, `finished_at` once it
+/// has finished.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Meta {
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// Output of a finished task, `stderr` is present only if it failed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResultData {
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+/// Response of `GET /get_status`, `result` is present once the task has finished.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatusResponse {
+    pub status: Status,
+    pub meta: Meta,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<ResultData>,
+}
+
+/// Response of `GET /get_task_count`: the number of tasks waiting in the queue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskCountResponse {
+    pub tasks: u32,
+}
+
+/// Response of `GET /capabilities`, the extensions of the protocol a server supports.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CapabilitiesResponse {
+    pub types: BTreeMap<TaskType, Encoding>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl CapabilitiesResponse {
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|known| known == feature)
+    }
+}
+
+/// Body of the error responses of the reference server.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn wire_format() {
+        let status: StatusResponse = serde_json::from_value(json!({
+            "status": "ERROR",
+            "meta": {"created_at": "2024-11-10 00:00:00Z", "started_at": "2024-11-10 00:00:01Z", "finished_at": "2024-11-10 00:00:02Z"},
+            "result": {"stdout": "out", "stderr": "err"},
+        }))
+        .unwrap();
+        assert_eq!(status.status, Status::Error);
+        assert!(status.status.is_finished());
+        assert_eq!(
+            status.result.as_ref().unwrap().stderr.as_deref(),
+            Some("err")
+        );
+
+        let waiting = json!({"status": "WAIT", "meta": {"created_at": "2024-11-10 00:00:00Z"}});
+        let status: StatusResponse = serde_json::from_value(waiting.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&status).unwrap(), waiting);
+
+        let request: CreateTaskRequest =
+            serde_json::from_value(json!({"type": "bin", "file": "AA==", "env": {"A": "1"}}))
+                .unwrap();
+        assert_eq!(request.r#type, TaskType::Bin);
+        assert_eq!(request.args, "");
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["env"],
+            json!({"A": "1"})
+        );
+
+        let capabilities: CapabilitiesResponse = serde_json::from_value(
+            json!({"types": {"python": "text", "wasm": "base64"}, "features": ["argv"]}),
+        )
+        .unwrap();
+        assert_eq!(
+            capabilities.types[&TaskType::Other("wasm".to_string())],
+            Encoding::Base64
+        );
+        assert!(capabilities.supports(FEATURE_ARGV) && !capabilities.supports(FEATURE_ENV));
+        assert!(serde_json::from_value::<StatusResponse>(json!({"status": "DONE"})).is_err());
+        assert_eq!(
+            serde_json::to_value(TaskType::Shell).unwrap(),
+            Value::from("shell")
+        );
+    }
+}
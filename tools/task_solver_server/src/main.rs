@@ -0,0 +1,45 @@
+use clap::Parser;
+use std::{process::ExitCode, sync::Arc};
+use tasks::Store;
+use tiny_http::Server;
+
+mod server;
+mod tasks;
+mod worker;
+
+#[derive(Parser)]
+#[command(name = "Task Solver server")]
+#[command(about = "Reference server running Python scripts and binaries sent by clients", long_about = None)]
+struct Cli {
+    /// Number of threads running the tasks.
+    #[arg(short, long, default_value = "4", value_parser = clap::value_parser!(u16).range(1..))]
+    workers: u16,
+
+    #[arg(short, long, default_value = "127.0.0.1")]
+    address: String,
+
+    #[arg(short, long, default_value = "8080")]
+    port: u16,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let server = match Server::http((cli.address.as_str(), cli.port)) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("Failed to listen on {}:{}: {err}", cli.address, cli.port);
+            return ExitCode::FAILURE;
+        }
+    };
+    let store = Arc::new(Store::default());
+    if let Err(err) = worker::spawn_workers(&store, cli.workers.into()) {
+        eprintln!("Failed to start workers: {err}");
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Listening on http://{}:{} with {} workers",
+        cli.address, cli.port, cli.workers
+    );
+    server::serve(&server, &store);
+    ExitCode::SUCCESS
+}
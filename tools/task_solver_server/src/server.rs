@@ -0,0 +1,214 @@
+use crate::tasks::{Program, Store};
+use base64::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use task_solver_protocol::{
+    CapabilitiesResponse, CreateTaskRequest, CreateTaskResponse, Encoding, ErrorResponse,
+    StatusRequest, TaskCountResponse, TaskType, FEATURE_ARGV, FEATURE_ENV, FEATURE_STDIN,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Task types the workers can run.
+const TASK_TYPES: [TaskType; 3] = [TaskType::Python, TaskType::Bin, TaskType::Shell];
+const FEATURES: [&str; 3] = [FEATURE_ARGV, FEATURE_STDIN, FEATURE_ENV];
+
+struct HttpError {
+    code: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(code: u16, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned>(request: &mut Request) -> Result<T, HttpError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|err| HttpError::new(400, format!("failed to read the body: {err}")))?;
+    serde_json::from_str(&body).map_err(|err| HttpError::new(400, format!("invalid body: {err}")))
+}
+
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("responses serialize to JSON")
+}
+
+fn create_task(request: &mut Request, store: &Store) -> Result<String, HttpError> {
+    let task: CreateTaskRequest = read_json(request)?;
+    let encoding = match task.r#type.encoding() {
+        Some(encoding) if TASK_TYPES.contains(&task.r#type) => encoding,
+        _ => {
+            let message = format!("unsupported task type \"{}\"", task.r#type);
+            return Err(HttpError::new(400, message));
+        }
+    };
+    let code = match encoding {
+        Encoding::Text => task.file.into_bytes(),
+        Encoding::Base64 => BASE64_STANDARD
+            .decode(task.file)
+            .map_err(|err| HttpError::new(400, format!("file is not valid base64: {err}")))?,
+    };
+    let args = match task.argv {
+        Some(_) if !task.args.is_empty() => {
+            return Err(HttpError::new(400, "only one of args and argv may be set"))
+        }
+        Some(argv) => argv,
+        None => task.args.split_whitespace().map(String::from).collect(),
+    };
+    let id = store.create(Program {
+        task_type: task.r#type,
+        code,
+        args,
+        stdin: task.stdin,
+        env: task.env.into_iter().collect(),
+    });
+    Ok(to_json(&CreateTaskResponse { id }))
+}
+
+fn get_status(request: &mut Request, store: &Store) -> Result<String, HttpError> {
+    let StatusRequest { id } = read_json(request)?;
+    store
+        .status(&id)
+        .map(|status| to_json(&status))
+        .ok_or_else(|| HttpError::new(404, format!("no task with id \"{id}\"")))
+}
+
+fn route(request: &mut Request, store: &Store) -> Result<String, HttpError> {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let method = request.method().clone();
+    match (method, path.as_str()) {
+        (Method::Post, "/create_task") => create_task(request, store),
+        (Method::Get, "/get_status") => get_status(request, store),
+        (Method::Get, "/get_task_count") => Ok(to_json(&TaskCountResponse {
+            tasks: store.waiting().try_into().unwrap_or(u32::MAX),
+        })),
+        (Method::Get, "/capabilities") => Ok(to_json(&CapabilitiesResponse {
+            types: TASK_TYPES
+                .into_iter()
+                .filter_map(|task_type| Some((task_type.clone(), task_type.encoding()?)))
+                .collect(),
+            features: FEATURES.map(String::from).to_vec(),
+        })),
+        (method, "/create_task" | "/get_status" | "/get_task_count" | "/capabilities") => Err(
+            HttpError::new(405, format!("method {method} is not allowed for {path}")),
+        ),
+        (_, path) => Err(HttpError::new(404, format!("unknown endpoint {path}"))),
+    }
+}
+
+/// Handles the requests until the server is unblocked or fails.
+pub fn serve(server: &Server, store: &Store) {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    for mut request in server.incoming_requests() {
+        let (code, body) = match route(&mut request, store) {
+            Ok(body) => (200, body),
+            Err(err) => (err.code, to_json(&ErrorResponse { error: err.message })),
+        };
+        let response = Response::from_string(body)
+            .with_status_code(code)
+            .with_header(content_type.clone());
+        // The client may have disconnected already, there is no one to report it to.
+        let _ = request.respond(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::spawn_workers;
+    use serde_json::{json, Value};
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let code = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (code, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn api() {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let address = server.server_addr().to_ip().unwrap();
+        let store = Arc::new(Store::default());
+        spawn_workers(&store, 2).unwrap();
+        {
+            let (server, store) = (Arc::clone(&server), Arc::clone(&store));
+            thread::spawn(move || serve(&server, &store));
+        }
+
+        let task =
+            json!({"type": "python", "file": "import sys\nprint(sys.argv[1])", "args": "hi"});
+        let (code, created) = request(address, "POST", "/create_task", &task.to_string());
+        assert_eq!(code, 200);
+        let id = created["id"].as_str().unwrap();
+
+        let started = Instant::now();
+        let status = loop {
+            let (code, status) = request(
+                address,
+                "GET",
+                "/get_status",
+                &json!({ "id": id }).to_string(),
+            );
+            assert_eq!(code, 200);
+            if status["status"] == "SUCCESS" || status["status"] == "ERROR" {
+                break status;
+            }
+            assert!(started.elapsed() < Duration::from_secs(10), "{status}");
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(status["status"], "SUCCESS");
+        assert_eq!(status["result"], json!({"stdout": "hi\n"}));
+        for key in ["created_at", "started_at", "finished_at"] {
+            assert!(status["meta"][key].is_string(), "{status}");
+        }
+
+        assert_eq!(
+            request(address, "GET", "/get_task_count", ""),
+            (200, json!({"tasks": 0}))
+        );
+        let (code, capabilities) = request(address, "GET", "/capabilities", "");
+        assert_eq!(code, 200);
+        assert_eq!(
+            capabilities["types"],
+            json!({"python": "text", "bin": "base64", "shell": "text"})
+        );
+        let both = json!({"type": "shell", "file": "", "args": "a", "argv": ["b"]}).to_string();
+        assert_eq!(request(address, "POST", "/create_task", &both).0, 400);
+        let unknown = json!({"id": "unknown"}).to_string();
+        assert_eq!(request(address, "GET", "/get_status", &unknown).0, 404);
+        let binary = json!({"type": "bin", "file": "not base64!"}).to_string();
+        assert_eq!(request(address, "POST", "/create_task", &binary).0, 400);
+        let wasm = json!({"type": "wasm", "file": ""}).to_string();
+        assert_eq!(request(address, "POST", "/create_task", &wasm).0, 400);
+        assert_eq!(request(address, "POST", "/get_task_count", "").0, 405);
+        assert_eq!(request(address, "GET", "/", "").0, 404);
+        server.unblock();
+    }
+}
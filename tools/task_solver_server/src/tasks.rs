@@ -0,0 +1,143 @@
+use chrono::Utc;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Condvar, Mutex},
+};
+use task_solver_protocol::{Meta, ResultData, Status, StatusResponse, TaskType, TIME_FORMAT};
+use uuid::Uuid;
+
+/// What a task runs: the decoded file, the arguments, the input and the environment.
+pub struct Program {
+    pub task_type: TaskType,
+    pub code: Vec<u8>,
+    pub args: Vec<String>,
+    pub stdin: Option<String>,
+    pub env: Vec<(String, String)>,
+}
+
+struct Task {
+    status: Status,
+    meta: Meta,
+    result: Option<ResultData>,
+    /// Taken by the worker that runs the task.
+    program: Option<Program>,
+}
+
+#[derive(Default)]
+struct State {
+    tasks: HashMap<String, Task>,
+    queue: VecDeque<String>,
+}
+
+/// Tasks of the server and the queue of the waiting ones, shared by the HTTP handler and the workers.
+#[derive(Default)]
+pub struct Store {
+    state: Mutex<State>,
+    queued: Condvar,
+}
+
+fn now() -> String {
+    Utc::now().format(TIME_FORMAT).to_string()
+}
+
+impl Store {
+    /// Queues a new task and returns its id.
+    pub fn create(&self, program: Program) -> String {
+        let id = Uuid::new_v4().to_string();
+        let task = Task {
+            status: Status::Wait,
+            meta: Meta {
+                created_at: now(),
+                started_at: None,
+                finished_at: None,
+            },
+            result: None,
+            program: Some(program),
+        };
+        let mut state = self.state.lock().unwrap();
+        state.tasks.insert(id.clone(), task);
+        state.queue.push_back(id.clone());
+        self.queued.notify_one();
+        id
+    }
+
+    pub fn status(&self, id: &str) -> Option<StatusResponse> {
+        let state = self.state.lock().unwrap();
+        state.tasks.get(id).map(|task| StatusResponse {
+            status: task.status,
+            meta: task.meta.clone(),
+            result: task.result.clone(),
+        })
+    }
+
+    /// Number of tasks waiting in the queue.
+    pub fn waiting(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    /// Blocks until a task is queued, then marks it as running and returns its program.
+    pub fn next(&self) -> (String, Program) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(id) = state.queue.pop_front() {
+                let task = state.tasks.get_mut(&id).expect("queued task exists");
+                task.status = Status::Running;
+                task.meta.started_at = Some(now());
+                let program = task.program.take().expect("queued task has a program");
+                return (id, program);
+            }
+            state = self.queued.wait(state).unwrap();
+        }
+    }
+
+    pub fn finish(&self, id: &str, status: Status, result: ResultData) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(task) = state.tasks.get_mut(id) {
+            task.status = status;
+            task.meta.finished_at = Some(now());
+            task.result = Some(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_lifecycle() {
+        let store = Store::default();
+        let id = store.create(Program {
+            task_type: TaskType::Python,
+            code: b"print(1)".to_vec(),
+            args: vec![],
+            stdin: None,
+            env: vec![],
+        });
+        let status = store.status(&id).unwrap();
+        assert_eq!(status.status, Status::Wait);
+        assert!(status.meta.started_at.is_none());
+        assert_eq!(store.waiting(), 1);
+
+        let (next_id, program) = store.next();
+        assert_eq!(next_id, id);
+        assert_eq!(program.code, b"print(1)");
+        assert_eq!(store.waiting(), 0);
+        let status = store.status(&id).unwrap();
+        assert_eq!(status.status, Status::Running);
+        assert!(status.meta.started_at.is_some() && status.meta.finished_at.is_none());
+
+        store.finish(
+            &id,
+            Status::Success,
+            ResultData {
+                stdout: "1\n".to_string(),
+                stderr: None,
+            },
+        );
+        let status = store.status(&id).unwrap();
+        assert_eq!(status.status, Status::Success);
+        assert!(status.meta.finished_at.is_some());
+        assert!(store.status("unknown").is_none());
+    }
+}
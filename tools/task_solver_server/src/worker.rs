@@ -0,0 +1,186 @@
+use crate::tasks::{Program, Store};
+use std::{
+    fs,
+    io::{self, Write},
+    process::{Child, Command, Output, Stdio},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+use task_solver_protocol::{ResultData, Status, TaskType};
+
+/// `errno` of exec on an executable that some process still has open for writing.
+const ETXTBSY: i32 = 26;
+const EXEC_ATTEMPTS: u32 = 10;
+
+/// Starts `count` threads, each running the queued tasks one by one.
+pub fn spawn_workers(store: &Arc<Store>, count: usize) -> io::Result<()> {
+    for i in 0..count {
+        let store = Arc::clone(store);
+        thread::Builder::new()
+            .name(format!("worker-{i}"))
+            .spawn(move || loop {
+                let (id, program) = store.next();
+                let (status, result) = run(&program);
+                store.finish(&id, status, result);
+            })?;
+    }
+    Ok(())
+}
+
+/// Runs the program in a temporary directory, `SUCCESS` if it exits with code 0.
+pub fn run(program: &Program) -> (Status, ResultData) {
+    match execute(program) {
+        Ok(output) if output.status.success() => (
+            Status::Success,
+            ResultData {
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr: None,
+            },
+        ),
+        Ok(output) => (
+            Status::Error,
+            ResultData {
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr: Some(String::from_utf8_lossy(&output.stderr).into_owned()),
+            },
+        ),
+        Err(err) => (
+            Status::Error,
+            ResultData {
+                stdout: String::new(),
+                stderr: Some(format!("failed to run the task: {err}")),
+            },
+        ),
+    }
+}
+
+fn execute(program: &Program) -> io::Result<Output> {
+    let dir = tempfile::tempdir()?;
+    let mut command = match &program.task_type {
+        TaskType::Python => {
+            let path = dir.path().join("main.py");
+            fs::write(&path, &program.code)?;
+            let mut command = Command::new("python3");
+            command.arg(path);
+            command
+        }
+        TaskType::Shell => {
+            let path = dir.path().join("main.sh");
+            fs::write(&path, &program.code)?;
+            let mut command = Command::new("sh");
+            command.arg(path);
+            command
+        }
+        TaskType::Bin => {
+            let path = dir.path().join("main");
+            fs::write(&path, &program.code)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+            }
+            Command::new(path)
+        }
+        TaskType::Other(name) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unknown task type \"{name}\""),
+            ))
+        }
+    };
+    command
+        .args(&program.args)
+        .envs(program.env.iter().map(|(key, value)| (key, value)))
+        .current_dir(dir.path())
+        .stdin(match program.stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = spawn(&mut command)?;
+    let writer = match (child.stdin.take(), program.stdin.clone()) {
+        // Written from another thread, the task may fill the output pipes before reading it all.
+        (Some(mut stdin), Some(input)) => Some(thread::spawn(move || {
+            // The task may exit without reading its input.
+            let _ = stdin.write_all(input.as_bytes());
+        })),
+        _ => None,
+    };
+    let output = child.wait_with_output();
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    output
+}
+
+fn spawn(command: &mut Command) -> io::Result<Child> {
+    // Another worker may fork while this binary is still open for writing, then exec fails
+    // until the forked process execs its own program.
+    let mut attempt = 1;
+    loop {
+        match command.spawn() {
+            Err(err) if err.raw_os_error() == Some(ETXTBSY) && attempt < EXEC_ATTEMPTS => {
+                attempt += 1;
+                thread::sleep(Duration::from_millis(10));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn python(code: &str, args: &[&str]) -> Program {
+        Program {
+            task_type: TaskType::Python,
+            code: code.as_bytes().to_vec(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            stdin: None,
+            env: vec![],
+        }
+    }
+
+    #[test]
+    fn python_tasks() {
+        let (status, result) = run(&python("import sys\nprint(sys.argv[1:])", &["a", "b"]));
+        assert_eq!(status, Status::Success);
+        assert_eq!(result.stdout, "['a', 'b']\n");
+        assert!(result.stderr.is_none());
+
+        let (status, result) = run(&python("print('partial')\nraise SystemExit('boom')", &[]));
+        assert_eq!(status, Status::Error);
+        assert_eq!(result.stdout, "partial\n");
+        assert_eq!(result.stderr.as_deref(), Some("boom\n"));
+    }
+
+    #[test]
+    fn shell_with_input() {
+        let (status, result) = run(&Program {
+            task_type: TaskType::Shell,
+            code: b"read line\necho \"$GREETING $line $1\"".to_vec(),
+            args: vec!["with spaces".to_string()],
+            stdin: Some("from stdin\n".to_string()),
+            env: vec![("GREETING".to_string(), "hello".to_string())],
+        });
+        assert_eq!(status, Status::Success);
+        assert_eq!(result.stdout, "hello from stdin with spaces\n");
+    }
+
+    #[test]
+    fn invalid_binary() {
+        let (status, result) = run(&Program {
+            task_type: TaskType::Bin,
+            code: b"not an executable".to_vec(),
+            args: vec![],
+            stdin: None,
+            env: vec![],
+        });
+        assert_eq!(status, Status::Error);
+        assert!(result.stderr.unwrap().starts_with("failed to run the task"));
+    }
+}